@@ -14,6 +14,65 @@ export interface QueryResult {
   lastInsertId: number;
 }
 
+/** The database types a bind value can be bound as explicitly with `typed`. */
+export type ParamType =
+  | "null"
+  | "bool"
+  | "int"
+  | "float"
+  | "text"
+  | "bytes"
+  | "json"
+  | "date"
+  | "time"
+  | "datetime"
+  | "timestamp";
+
+export interface TypedParam {
+  $type: ParamType;
+  $value: unknown;
+}
+
+/**
+ * **typed**
+ *
+ * Binds `value` as the given database type instead of inferring it from the JavaScript value.
+ * `int` and `float` values can be passed as strings to not lose precision,
+ * `date`, `time`, `datetime` and `timestamp` values are ISO 8601 strings.
+ *
+ * @example
+ * ```ts
+ * await db.execute("INSERT INTO events (id, day, created_at) VALUES ($1, $2, $3)", [
+ *   typed("int", "9007199254740993"),
+ *   typed("date", "2024-01-05"),
+ *   typed("timestamp", new Date().toISOString()),
+ * ]);
+ * ```
+ */
+export function typed(type: ParamType, value: unknown): TypedParam {
+  return { $type: type, $value: value ?? null };
+}
+
+/** `Uint8Array`s would be serialized as objects, so they are sent as explicit byte arrays. */
+function serializeValues(bindValues?: unknown[]): unknown[] {
+  return (bindValues ?? []).map((value) =>
+    value instanceof Uint8Array ? typed("bytes", Array.from(value)) : value,
+  );
+}
+
+export interface MigrationInfo {
+  version: number;
+  /** `null` for applied migrations that aren't registered anymore. */
+  description: string | null;
+  /** The hex encoded checksum of the up migration. */
+  checksum: string;
+}
+
+export interface MigrationStatus {
+  applied: MigrationInfo[];
+  pending: MigrationInfo[];
+}
+
 /**
  * **Database**
  *
@@ -107,7 +166,7 @@ export default class Database {
       {
         db: this.path,
         query,
-        values: bindValues ?? [],
+        values: serializeValues(bindValues),
       },
     );
     return {
@@ -137,12 +196,117 @@ export default class Database {
     const result = await invoke<T>("plugin:sql|select", {
       db: this.path,
       query,
-      values: bindValues ?? [],
+      values: serializeValues(bindValues),
     });
 
     return result;
   }
 
+  /**
+   * **selectStream**
+   *
+   * Opens a cursor over the rows of a SELECT query. The rows are read in batches with `Cursor.fetchNext`
+   * instead of being loaded all at once.
+   *
+   * The cursor is closed along with the database, or when the window is destroyed.
+   *
+   * @example
+   * ```ts
+   * const cursor = await db.selectStream("SELECT * from todos");
+   * let rows;
+   * while ((rows = await cursor.fetchNext<Todo>(100)).length) {
+   *   render(rows);
+   * }
+   * await cursor.close();
+   * ```
+   */
+  async selectStream(query: string, bindValues?: unknown[]): Promise<Cursor> {
+    const id = await invoke<number>("plugin:sql|select_stream", {
+      db: this.path,
+      query,
+      values: serializeValues(bindValues),
+    });
+    return new Cursor(id);
+  }
+
+  /**
+   * **begin**
+   *
+   * Starts a transaction. Queries run through the returned `Transaction` only take effect once it's committed.
+   *
+   * The transaction is rolled back if it's still open when the database is closed or the window is destroyed.
+   *
+   * @example
+   * ```ts
+   * const tx = await db.begin();
+   * try {
+   *   await tx.execute("UPDATE accounts SET balance = balance - $1 WHERE id = $2", [amount, from]);
+   *   await tx.execute("UPDATE accounts SET balance = balance + $1 WHERE id = $2", [amount, to]);
+   *   await tx.commit();
+   * } catch (e) {
+   *   await tx.rollback();
+   *   throw e;
+   * }
+   * ```
+   */
+  async begin(): Promise<Transaction> {
+    const id = await invoke<number>("plugin:sql|begin", {
+      db: this.path,
+    });
+    return new Transaction(this.path, id);
+  }
+
+  /**
+   * **migrationStatus**
+   *
+   * Lists the applied and the pending migrations of the database.
+   *
+   * @example
+   * ```ts
+   * const { applied, pending } = await db.migrationStatus();
+   * ```
+   */
+  async migrationStatus(): Promise<MigrationStatus> {
+    return await invoke<MigrationStatus>("plugin:sql|migration_status", {
+      db: this.path,
+    });
+  }
+
+  /**
+   * **migrateTo**
+   *
+   * Applies or reverts migrations until `version` is the latest applied migration.
+   * Reverting a migration requires a `Down` migration with the same version.
+   *
+   * @example
+   * ```ts
+   * await db.migrateTo(2);
+   * ```
+   */
+  async migrateTo(version: number): Promise<void> {
+    await invoke("plugin:sql|migrate_to", {
+      db: this.path,
+      version,
+    });
+  }
+
+  /**
+   * **undoMigrations**
+   *
+   * Reverts the last `count` applied migrations using their `Down` migrations.
+   *
+   * @example
+   * ```ts
+   * await db.undoMigrations(1);
+   * ```
+   */
+  async undoMigrations(count: number): Promise<void> {
+    await invoke("plugin:sql|undo_migrations", {
+      db: this.path,
+      count,
+    });
+  }
+
   /**
    * **close**
    *
@@ -161,3 +325,107 @@ export default class Database {
     return success;
   }
 }
+
+/**
+ * **Transaction**
+ *
+ * An open transaction on a database, created with `Database.begin`.
+ */
+export class Transaction {
+  path: string;
+  id: number;
+  constructor(path: string, id: number) {
+    this.path = path;
+    this.id = id;
+  }
+
+  /**
+   * **execute**
+   *
+   * Passes a SQL expression to the database for execution inside of the transaction, see `Database.execute`.
+   */
+  async execute(query: string, bindValues?: unknown[]): Promise<QueryResult> {
+    const [rowsAffected, lastInsertId] = await invoke<[number, number]>(
+      "plugin:sql|execute",
+      {
+        db: this.path,
+        query,
+        values: serializeValues(bindValues),
+        transactionId: this.id,
+      },
+    );
+    return {
+      lastInsertId,
+      rowsAffected,
+    };
+  }
+
+  /**
+   * **select**
+   *
+   * Passes in a SELECT query to the database for execution inside of the transaction, see `Database.select`.
+   */
+  async select<T>(query: string, bindValues?: unknown[]): Promise<T> {
+    const result = await invoke<T>("plugin:sql|select", {
+      db: this.path,
+      query,
+      values: serializeValues(bindValues),
+      transactionId: this.id,
+    });
+
+    return result;
+  }
+
+  /**
+   * **commit**
+   *
+   * Commits the transaction, it can't be used afterwards.
+   */
+  async commit(): Promise<void> {
+    await invoke("plugin:sql|commit", { transactionId: this.id });
+  }
+
+  /**
+   * **rollback**
+   *
+   * Rolls back the transaction, it can't be used afterwards.
+   */
+  async rollback(): Promise<void> {
+    await invoke("plugin:sql|rollback", { transactionId: this.id });
+  }
+}
+
+/**
+ * **Cursor**
+ *
+ * A cursor over the rows of a query, created with `Database.selectStream`.
+ */
+export class Cursor {
+  id: number;
+  constructor(id: number) {
+    this.id = id;
+  }
+
+  /**
+   * **fetchNext**
+   *
+   * Returns the next `count` rows, fewer once all rows were read.
+   */
+  async fetchNext<T>(count: number): Promise<T[]> {
+    return await invoke<T[]>("plugin:sql|fetch_next", {
+      cursorId: this.id,
+      count,
+    });
+  }
+
+  /**
+   * **close**
+   *
+   * Closes the cursor, releasing its connection.
+   */
+  async close(): Promise<boolean> {
+    return await invoke<boolean>("plugin:sql|close_cursor", {
+      cursorId: this.id,
+    });
+  }
+}
@@ -0,0 +1,128 @@
+// Copyright 2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use futures_util::StreamExt;
+use serde_json::Value as JsonValue;
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+use crate::{param::Param, wrapper::DbPool, Error};
+
+pub(crate) type CursorId = u32;
+
+type Rows = Vec<HashMap<String, JsonValue>>;
+
+/// A request for the next `usize` rows of a cursor.
+type Fetch = (usize, oneshot::Sender<Result<Rows, Error>>);
+
+/// The most rows a batch allocates room for upfront, as the requested count comes from the frontend.
+const MAX_BATCH_CAPACITY: usize = 1024;
+
+/// A query streaming its rows from a background task, which ends once the cursor is dropped.
+struct Cursor {
+    db: String,
+    window: String,
+    requests: mpsc::Sender<Fetch>,
+}
+
+impl Cursor {
+    fn open(db: String, window: String, pool: DbPool, query: String, values: Vec<Param>) -> Self {
+        let (requests, mut receiver) = mpsc::channel::<Fetch>(1);
+
+        tauri::async_runtime::spawn(async move {
+            let mut rows = pool.fetch(&query, values);
+            while let Some((count, reply)) = receiver.recv().await {
+                let mut batch = Vec::with_capacity(count.min(MAX_BATCH_CAPACITY));
+                let mut result = Ok(());
+                while batch.len() < count {
+                    match rows.next().await {
+                        Some(Ok(row)) => batch.push(row),
+                        Some(Err(e)) => {
+                            result = Err(e);
+                            break;
+                        }
+                        None => break,
+                    }
+                }
+                let _ = reply.send(result.map(|_| batch));
+            }
+        });
+
+        Self {
+            db,
+            window,
+            requests,
+        }
+    }
+}
+
+/// The open cursors of all databases.
+#[derive(Default)]
+pub(crate) struct DbCursors {
+    next_id: AtomicU32,
+    cursors: Mutex<HashMap<CursorId, Cursor>>,
+}
+
+impl DbCursors {
+    /// Starts streaming the rows of `query` on the database `db` for `window`.
+    pub(crate) async fn open(
+        &self,
+        db: String,
+        window: String,
+        pool: DbPool,
+        query: String,
+        values: Vec<Param>,
+    ) -> CursorId {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let cursor = Cursor::open(db, window, pool, query, values);
+        self.cursors.lock().await.insert(id, cursor);
+        id
+    }
+
+    /// Returns up to `count` rows of the cursor, fewer once the result set is exhausted.
+    pub(crate) async fn fetch_next(&self, id: CursorId, count: usize) -> Result<Rows, Error> {
+        let requests = self
+            .cursors
+            .lock()
+            .await
+            .get(&id)
+            .map(|cursor| cursor.requests.clone())
+            .ok_or(Error::CursorNotFound(id))?;
+
+        let (reply, rows) = oneshot::channel();
+        requests
+            .send((count, reply))
+            .await
+            .map_err(|_| Error::CursorNotFound(id))?;
+        rows.await.map_err(|_| Error::CursorNotFound(id))?
+    }
+
+    pub(crate) async fn close(&self, id: CursorId) -> bool {
+        self.cursors.lock().await.remove(&id).is_some()
+    }
+
+    /// Closes all cursors streaming from one of the `dbs`, e.g. before closing their pools.
+    pub(crate) async fn close_dbs(&self, dbs: &[String]) {
+        self.cursors
+            .lock()
+            .await
+            .retain(|_, cursor| !dbs.contains(&cursor.db));
+    }
+
+    /// Closes all cursors opened by a window, e.g. once it's destroyed.
+    pub(crate) async fn close_window(&self, window: &str) {
+        self.cursors
+            .lock()
+            .await
+            .retain(|_, cursor| cursor.window != window);
+    }
+
+    pub(crate) async fn close_all(&self) {
+        self.cursors.lock().await.clear();
+    }
+}
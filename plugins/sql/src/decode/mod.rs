@@ -1,15 +1,47 @@
+use serde_json::Value as JsonValue;
+
 #[cfg(feature = "mysql")]
-mod mysql;
+pub(crate) mod mysql;
 #[cfg(feature = "postgres")]
-mod postgres;
+pub(crate) mod postgres;
 #[cfg(feature = "sqlite")]
-mod sqlite;
+pub(crate) mod sqlite;
+
+/// The largest integer a JavaScript number represents exactly.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+/// Encodes integers that don't fit in a JavaScript number as strings, to not lose precision.
+pub(crate) fn integer(value: i64) -> JsonValue {
+    if (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&value) {
+        JsonValue::from(value)
+    } else {
+        JsonValue::String(value.to_string())
+    }
+}
 
 #[cfg(feature = "mysql")]
-pub(crate) use mysql::to_json;
+pub(crate) fn unsigned(value: u64) -> JsonValue {
+    if value <= MAX_SAFE_INTEGER as u64 {
+        JsonValue::from(value)
+    } else {
+        JsonValue::String(value.to_string())
+    }
+}
 
-#[cfg(feature = "postgres")]
-pub(crate) use postgres::to_json;
+/// Encodes `NaN` and the infinities, which JSON numbers can't represent, as strings.
+pub(crate) fn float(value: f64) -> JsonValue {
+    if value.is_finite() {
+        JsonValue::from(value)
+    } else {
+        JsonValue::String(value.to_string())
+    }
+}
 
-#[cfg(feature = "sqlite")]
-pub(crate) use sqlite::to_json;
+pub(crate) fn bytes(value: Vec<u8>) -> JsonValue {
+    JsonValue::Array(value.into_iter().map(JsonValue::from).collect())
+}
+
+/// Encodes values without a JSON equivalent, like decimals and dates, as their string representation.
+pub(crate) fn string(value: impl ToString) -> JsonValue {
+    JsonValue::String(value.to_string())
+}
@@ -1,7 +1,12 @@
-use serde_json::Value as JsonValue;
-use sqlx::{postgres::PgValueRef, TypeInfo, Value, ValueRef};
+use serde_json::{json, Value as JsonValue};
+use sqlx::{
+    postgres::{types::PgInterval, PgValue, PgValueRef},
+    types::{ipnetwork::IpNetwork, BigDecimal, Uuid},
+    Decode, Postgres, Type, TypeInfo, Value, ValueRef,
+};
 use time::{Date, OffsetDateTime, PrimitiveDateTime, Time};
 
+use super::{bytes, float, integer, string};
 use crate::Error;
 
 pub(crate) fn to_json(v: PgValueRef) -> Result<JsonValue, Error> {
@@ -9,95 +14,82 @@ pub(crate) fn to_json(v: PgValueRef) -> Result<JsonValue, Error> {
         return Ok(JsonValue::Null);
     }
 
-    let res = match v.type_info().name() {
-        "CHAR" | "VARCHAR" | "TEXT" | "NAME" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode() {
-                JsonValue::String(v)
-            } else {
-                JsonValue::Null
-            }
-        }
-        "FLOAT4" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<f32>() {
-                JsonValue::from(v)
-            } else {
-                JsonValue::Null
-            }
-        }
-        "FLOAT8" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<f64>() {
-                JsonValue::from(v)
-            } else {
-                JsonValue::Null
-            }
-        }
-        "INT2" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<i16>() {
-                JsonValue::Number(v.into())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "INT4" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<i32>() {
-                JsonValue::Number(v.into())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "INT8" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<i64>() {
-                JsonValue::Number(v.into())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "BOOL" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode() {
-                JsonValue::Bool(v)
-            } else {
-                JsonValue::Null
-            }
-        }
-        "DATE" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<Date>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "TIME" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<Time>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "TIMESTAMP" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<PrimitiveDateTime>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "TIMESTAMPTZ" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<OffsetDateTime>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "JSON" | "JSONB" => ValueRef::to_owned(&v).try_decode().unwrap_or_default(),
-        "BYTEA" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<Vec<u8>>() {
-                JsonValue::Array(v.into_iter().map(|n| JsonValue::Number(n.into())).collect())
-            } else {
-                JsonValue::Null
-            }
-        }
+    let value = ValueRef::to_owned(&v);
+    let type_info = v.type_info();
+    let name = type_info.name();
+    match name.strip_suffix("[]") {
+        Some(element) => to_json_array(&value, element),
+        None => to_json_scalar(&value, name),
+    }
+}
+
+fn interval(value: PgInterval) -> JsonValue {
+    json!({
+        "months": value.months,
+        "days": value.days,
+        "microseconds": integer(value.microseconds),
+    })
+}
+
+fn to_json_scalar(value: &PgValue, name: &str) -> Result<JsonValue, Error> {
+    let res = match name {
+        "CHAR" | "VARCHAR" | "TEXT" | "NAME" => JsonValue::String(value.try_decode()?),
+        "FLOAT4" => float(value.try_decode::<f32>()?.into()),
+        "FLOAT8" => float(value.try_decode()?),
+        "INT2" => JsonValue::from(value.try_decode::<i16>()?),
+        "INT4" => JsonValue::from(value.try_decode::<i32>()?),
+        "INT8" => integer(value.try_decode()?),
+        "NUMERIC" => string(value.try_decode::<BigDecimal>()?),
+        "BOOL" => JsonValue::Bool(value.try_decode()?),
+        "DATE" => string(value.try_decode::<Date>()?),
+        "TIME" => string(value.try_decode::<Time>()?),
+        "TIMESTAMP" => string(value.try_decode::<PrimitiveDateTime>()?),
+        "TIMESTAMPTZ" => string(value.try_decode::<OffsetDateTime>()?),
+        "INTERVAL" => interval(value.try_decode()?),
+        "UUID" => string(value.try_decode::<Uuid>()?),
+        "INET" | "CIDR" => string(value.try_decode::<IpNetwork>()?),
+        "JSON" | "JSONB" => value.try_decode()?,
+        "BYTEA" => bytes(value.try_decode()?),
         "VOID" => JsonValue::Null,
-        _ => return Err(Error::UnsupportedDatatype(v.type_info().name().to_string())),
+        _ => return Err(Error::UnsupportedDatatype(name.to_string())),
     };
 
     Ok(res)
 }
+
+/// Decodes an array of `T`, mapping its elements with `to_json`.
+fn array<'r, T>(value: &'r PgValue, to_json: fn(T) -> JsonValue) -> Result<JsonValue, Error>
+where
+    Vec<Option<T>>: Decode<'r, Postgres> + Type<Postgres>,
+{
+    Ok(JsonValue::Array(
+        value
+            .try_decode::<Vec<Option<T>>>()?
+            .into_iter()
+            .map(|element| element.map_or(JsonValue::Null, to_json))
+            .collect(),
+    ))
+}
+
+fn to_json_array(value: &PgValue, element: &str) -> Result<JsonValue, Error> {
+    match element {
+        "CHAR" | "VARCHAR" | "TEXT" | "NAME" => array(value, JsonValue::String),
+        "FLOAT4" => array(value, |v: f32| float(v.into())),
+        "FLOAT8" => array(value, float),
+        "INT2" => array::<i16>(value, JsonValue::from),
+        "INT4" => array::<i32>(value, JsonValue::from),
+        "INT8" => array(value, integer),
+        "NUMERIC" => array(value, |v: BigDecimal| string(v)),
+        "BOOL" => array(value, JsonValue::Bool),
+        "DATE" => array(value, |v: Date| string(v)),
+        "TIME" => array(value, |v: Time| string(v)),
+        "TIMESTAMP" => array(value, |v: PrimitiveDateTime| string(v)),
+        "TIMESTAMPTZ" => array(value, |v: OffsetDateTime| string(v)),
+        "INTERVAL" => array(value, interval),
+        "UUID" => array(value, |v: Uuid| string(v)),
+        "INET" | "CIDR" => array(value, |v: IpNetwork| string(v)),
+        "JSON" | "JSONB" => array(value, |v: JsonValue| v),
+        "BYTEA" => array(value, bytes),
+        _ => Err(Error::UnsupportedDatatype(format!("{element}[]"))),
+    }
+}
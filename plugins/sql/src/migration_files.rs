@@ -0,0 +1,94 @@
+// Copyright 2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{borrow::Cow, fs, path::Path};
+
+use sqlx::migrate::{Migration as SqlxMigration, MigrationType};
+
+use crate::{Error, MigrationKind};
+
+/// Parses a `<version>_<description>.up.sql` or `<version>_<description>.down.sql` file name.
+///
+/// Returns `None` for files that aren't SQL files.
+fn parse_file_name(name: &str) -> Result<Option<(i64, String, MigrationKind)>, Error> {
+    let (stem, kind) = if let Some(stem) = name.strip_suffix(".up.sql") {
+        (stem, MigrationKind::Up)
+    } else if let Some(stem) = name.strip_suffix(".down.sql") {
+        (stem, MigrationKind::Down)
+    } else if let Some(stem) = name.strip_suffix(".sql") {
+        (stem, MigrationKind::Up)
+    } else {
+        return Ok(None);
+    };
+
+    let (version, description) = stem
+        .split_once('_')
+        .ok_or_else(|| Error::InvalidMigrationFileName(name.to_string()))?;
+    let version = version
+        .parse()
+        .map_err(|_| Error::InvalidMigrationFileName(name.to_string()))?;
+
+    Ok(Some((version, description.replace('_', " "), kind)))
+}
+
+fn file_name(path: &Path) -> Result<&str, Error> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| Error::InvalidMigrationFileName(path.display().to_string()))
+}
+
+/// Sorts the migrations by version, each up migration before its down migration.
+pub(crate) fn sorted(mut migrations: Vec<SqlxMigration>) -> Vec<SqlxMigration> {
+    migrations.sort_by_key(|migration| {
+        (
+            migration.version,
+            migration.migration_type.is_down_migration(),
+        )
+    });
+    migrations
+}
+
+/// Reads the migration files of the directory at `path`.
+pub(crate) fn read_dir(path: &Path) -> Result<Vec<SqlxMigration>, Error> {
+    let mut migrations = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        if let Some((version, description, kind)) = parse_file_name(file_name(&path)?)? {
+            migrations.push(SqlxMigration::new(
+                version,
+                Cow::Owned(description),
+                MigrationType::from(kind),
+                Cow::Owned(fs::read_to_string(&path)?),
+            ));
+        }
+    }
+    Ok(sorted(migrations))
+}
+
+/// Reads the migration files of a directory embedded with `include_dir!`.
+#[cfg(feature = "include_dir")]
+pub(crate) fn read_embedded(dir: &include_dir::Dir<'static>) -> Result<Vec<SqlxMigration>, Error> {
+    let mut migrations = Vec::new();
+    for file in dir.files() {
+        let name = file_name(file.path())?;
+        if let Some((version, description, kind)) = parse_file_name(name)? {
+            let sql = file.contents_utf8().ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("migration {name} isn't valid UTF-8"),
+                )
+            })?;
+            migrations.push(SqlxMigration::new(
+                version,
+                Cow::Owned(description),
+                MigrationType::from(kind),
+                Cow::Borrowed(sql),
+            ));
+        }
+    }
+    Ok(sorted(migrations))
+}
@@ -0,0 +1,158 @@
+// Copyright 2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use serde::{de::Error as DeError, Deserialize, Deserializer};
+use serde_json::Value as JsonValue;
+use time::{
+    format_description::well_known::Iso8601, Date, OffsetDateTime, PrimitiveDateTime, Time,
+};
+
+/// A query parameter, bound as the database type matching its JSON value.
+///
+/// The type can be set explicitly with a `{ "$type": "<type>", "$value": <value> }` envelope,
+/// see [`Param::from_hint`] for the supported types. Objects with other keys besides `$type` and `$value`
+/// are bound as JSON.
+#[derive(Debug)]
+pub(crate) enum Param {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Bytes(Vec<u8>),
+    Json(JsonValue),
+    Date(Date),
+    Time(Time),
+    DateTime(PrimitiveDateTime),
+    Timestamp(OffsetDateTime),
+}
+
+#[derive(Deserialize)]
+struct TypeHint {
+    #[serde(rename = "$type")]
+    kind: String,
+    #[serde(rename = "$value", default)]
+    value: JsonValue,
+}
+
+/// Returns `true` if `map` is exactly a `{ "$type", "$value" }` type hint envelope.
+fn is_hint(map: &serde_json::Map<String, JsonValue>) -> bool {
+    map.len() == 2 && map.contains_key("$type") && map.contains_key("$value")
+}
+
+impl Param {
+    /// Infers the type of `value`:
+    /// integers, floats and booleans are bound natively, strings as text and everything else as JSON.
+    ///
+    /// Binary data, dates and times are only bound as such with a type hint.
+    fn from_value(value: JsonValue) -> Result<Self, String> {
+        let param = match value {
+            JsonValue::Null => Self::Null,
+            JsonValue::Bool(value) => Self::Bool(value),
+            JsonValue::Number(number) => {
+                if let Some(value) = number.as_i64() {
+                    Self::Int(value)
+                } else if number.is_u64() {
+                    return Err(format!(
+                        "integer {number} doesn't fit in a 64-bit signed integer"
+                    ));
+                } else {
+                    Self::Float(
+                        number
+                            .as_f64()
+                            .ok_or_else(|| format!("unsupported number {number}"))?,
+                    )
+                }
+            }
+            JsonValue::String(value) => Self::Text(value),
+            JsonValue::Object(map) if is_hint(&map) => {
+                let hint = TypeHint::deserialize(JsonValue::Object(map))
+                    .map_err(|e| format!("invalid type hint: {e}"))?;
+                Self::from_hint(&hint.kind, hint.value)?
+            }
+            value => Self::Json(value),
+        };
+        Ok(param)
+    }
+
+    /// Binds `value` as `kind`, one of
+    /// `null`, `bool`, `int`, `float`, `text`, `bytes`, `json`, `date`, `time`, `datetime` or `timestamp`.
+    ///
+    /// Integers and floats can be given as strings to not lose precision in JavaScript,
+    /// dates and times are ISO 8601 strings.
+    fn from_hint(kind: &str, value: JsonValue) -> Result<Self, String> {
+        let invalid = |value: &JsonValue| format!("invalid {kind} parameter: {value}");
+        let string = |value: &JsonValue| {
+            value
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| invalid(value))
+        };
+
+        let param = match kind {
+            "null" => Self::Null,
+            "bool" => Self::Bool(value.as_bool().ok_or_else(|| invalid(&value))?),
+            "int" => Self::Int(match &value {
+                JsonValue::String(s) => s.parse().map_err(|_| invalid(&value))?,
+                _ => value.as_i64().ok_or_else(|| invalid(&value))?,
+            }),
+            "float" => Self::Float(match &value {
+                JsonValue::String(s) => s.parse().map_err(|_| invalid(&value))?,
+                _ => value.as_f64().ok_or_else(|| invalid(&value))?,
+            }),
+            "text" => Self::Text(string(&value)?),
+            "bytes" => Self::Bytes(Vec::deserialize(&value).map_err(|_| invalid(&value))?),
+            "json" => Self::Json(value),
+            "date" => Self::Date(
+                Date::parse(&string(&value)?, &Iso8601::DEFAULT).map_err(|_| invalid(&value))?,
+            ),
+            "time" => Self::Time(
+                Time::parse(&string(&value)?, &Iso8601::DEFAULT).map_err(|_| invalid(&value))?,
+            ),
+            "datetime" => Self::DateTime(
+                PrimitiveDateTime::parse(&string(&value)?, &Iso8601::DEFAULT)
+                    .map_err(|_| invalid(&value))?,
+            ),
+            "timestamp" => Self::Timestamp(
+                OffsetDateTime::parse(&string(&value)?, &Iso8601::DEFAULT)
+                    .map_err(|_| invalid(&value))?,
+            ),
+            _ => return Err(format!("unknown parameter type {kind}")),
+        };
+        Ok(param)
+    }
+}
+
+impl<'de> Deserialize<'de> for Param {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::from_value(JsonValue::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn binds_type_hints() {
+        let param: Param =
+            serde_json::from_value(json!({ "$type": "int", "$value": "42" })).unwrap();
+        assert!(matches!(param, Param::Int(42)));
+    }
+
+    #[test]
+    fn binds_objects_with_a_type_key_as_json() {
+        let value = json!({ "$type": "MyApp.Models.Todo, MyApp", "title": "first" });
+        let param: Param = serde_json::from_value(value.clone()).unwrap();
+        assert!(matches!(param, Param::Json(json) if json == value));
+
+        let value = json!({ "$type": "Todo" });
+        let param: Param = serde_json::from_value(value.clone()).unwrap();
+        assert!(matches!(param, Param::Json(json) if json == value));
+    }
+}
@@ -7,34 +7,27 @@ use serde::{ser::Serializer, Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use sqlx::{
     error::BoxDynError,
-    migrate::{
-        MigrateDatabase, Migration as SqlxMigration, MigrationSource, MigrationType, Migrator,
-    },
-    Column, Pool, Row,
+    migrate::{Migration as SqlxMigration, MigrationSource, MigrationType, Migrator},
 };
 use tauri::{
     command,
     plugin::{Builder as PluginBuilder, TauriPlugin},
-    AppHandle, Manager, RunEvent, Runtime, State,
+    AppHandle, Manager, RunEvent, Runtime, State, Window, WindowEvent,
 };
 use tokio::sync::Mutex;
 
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
 
 #[cfg(feature = "sqlite")]
-use std::{fs::create_dir_all, path::PathBuf};
-
-#[cfg(feature = "sqlite")]
-type Db = sqlx::sqlite::Sqlite;
-#[cfg(feature = "mysql")]
-type Db = sqlx::mysql::MySql;
-#[cfg(feature = "postgres")]
-type Db = sqlx::postgres::Postgres;
-
-#[cfg(feature = "sqlite")]
-type LastInsertId = i64;
-#[cfg(not(feature = "sqlite"))]
-type LastInsertId = u64;
+use std::path::PathBuf;
+
+use crate::{
+    cursor::{CursorId, DbCursors},
+    migration_files,
+    param::Param,
+    transaction::{DbTransactions, TransactionId},
+    wrapper::{DbPool, LastInsertId},
+};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -46,6 +39,26 @@ pub enum Error {
     DatabaseNotLoaded(String),
     #[error("unsupported datatype: {0}")]
     UnsupportedDatatype(String),
+    #[error("invalid connection url: {0}")]
+    InvalidDbUrl(String),
+    #[error("transaction {0} not found")]
+    TransactionNotFound(TransactionId),
+    #[error("cursor {0} not found")]
+    CursorNotFound(CursorId),
+    #[error("migration {0} can't be undone, it has no down migration")]
+    IrreversibleMigration(i64),
+    #[error("can't undo {count} migrations, only {applied} are applied")]
+    NotEnoughMigrations { count: usize, applied: usize },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(
+        "invalid migration file name {0}, expected <version>_<description>.up.sql or .down.sql"
+    )]
+    InvalidMigrationFileName(String),
+    #[error("migration {version} of {db} is registered twice")]
+    DuplicateMigration { db: String, version: i64 },
+    #[error("down migration {version} of {db} has no up migration")]
+    MissingUpMigration { db: String, version: i64 },
 }
 
 impl Serialize for Error {
@@ -62,7 +75,7 @@ type Result<T> = std::result::Result<T, Error>;
 #[cfg(feature = "sqlite")]
 /// Resolves the App's **file path** from the `AppHandle` context
 /// object
-fn app_path<R: Runtime>(app: &AppHandle<R>) -> PathBuf {
+pub(crate) fn app_path<R: Runtime>(app: &AppHandle<R>) -> PathBuf {
     #[allow(deprecated)] // FIXME: Change to non-deprecated function in Tauri v2
     app.path_resolver()
         .app_dir()
@@ -72,7 +85,7 @@ fn app_path<R: Runtime>(app: &AppHandle<R>) -> PathBuf {
 #[cfg(feature = "sqlite")]
 /// Maps the user supplied DB connection string to a connection string
 /// with a fully qualified file path to the App's designed "app_path"
-fn path_mapper(mut app_path: PathBuf, connection_string: &str) -> String {
+pub(crate) fn path_mapper(mut app_path: PathBuf, connection_string: &str) -> String {
     app_path.push(
         connection_string
             .split_once(':')
@@ -89,7 +102,7 @@ fn path_mapper(mut app_path: PathBuf, connection_string: &str) -> String {
 }
 
 #[derive(Default)]
-struct DbInstances(Mutex<HashMap<String, Pool<Db>>>);
+struct DbInstances(Mutex<HashMap<String, DbPool>>);
 
 struct Migrations(Mutex<HashMap<String, MigrationList>>);
 
@@ -99,7 +112,7 @@ pub struct PluginConfig {
     preload: Vec<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MigrationKind {
     Up,
     Down,
@@ -115,7 +128,9 @@ impl From<MigrationKind> for MigrationType {
 }
 
 /// A migration definition.
-#[derive(Debug)]
+///
+/// A `Down` migration reverts the `Up` migration with the same version.
+#[derive(Debug, Clone)]
 pub struct Migration {
     pub version: i64,
     pub description: &'static str,
@@ -123,51 +138,134 @@ pub struct Migration {
     pub kind: MigrationKind,
 }
 
-#[derive(Debug)]
-struct MigrationList(Vec<Migration>);
+impl From<Migration> for SqlxMigration {
+    fn from(migration: Migration) -> Self {
+        Self::new(
+            migration.version,
+            migration.description.into(),
+            migration.kind.into(),
+            migration.sql.into(),
+        )
+    }
+}
+
+/// The migrations registered for a database, in the order they were registered.
+#[derive(Debug, Clone, Default)]
+struct MigrationList(Vec<SqlxMigration>);
+
+impl MigrationList {
+    fn contains(&self, version: i64, kind: MigrationKind) -> bool {
+        let migration_type = MigrationType::from(kind);
+        self.0.iter().any(|migration| {
+            migration.version == version && migration.migration_type == migration_type
+        })
+    }
+
+    /// The migrations up to and including `version`.
+    fn up_to(&self, version: i64) -> Self {
+        Self(
+            self.0
+                .iter()
+                .filter(|migration| migration.version <= version)
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Registers more migrations for the database `db`, keeping the list sorted by version.
+    ///
+    /// Every version can only be registered once, and every down migration needs an up migration
+    /// with the same version.
+    fn extend(&mut self, db: &str, migrations: Vec<SqlxMigration>) -> Result<()> {
+        let mut list = self.clone();
+        for migration in migrations {
+            let kind = if migration.migration_type.is_down_migration() {
+                MigrationKind::Down
+            } else {
+                MigrationKind::Up
+            };
+            if list.contains(migration.version, kind) {
+                return Err(Error::DuplicateMigration {
+                    db: db.to_string(),
+                    version: migration.version,
+                });
+            }
+            list.0.push(migration);
+        }
+        list.0 = migration_files::sorted(list.0);
+
+        for migration in &list.0 {
+            if migration.migration_type.is_down_migration()
+                && !list.contains(migration.version, MigrationKind::Up)
+            {
+                return Err(Error::MissingUpMigration {
+                    db: db.to_string(),
+                    version: migration.version,
+                });
+            }
+        }
+
+        *self = list;
+        Ok(())
+    }
+}
 
 impl MigrationSource<'static> for MigrationList {
     fn resolve(self) -> BoxFuture<'static, std::result::Result<Vec<SqlxMigration>, BoxDynError>> {
         Box::pin(async move {
-            let mut migrations = Vec::new();
-            for migration in self.0 {
-                if matches!(migration.kind, MigrationKind::Up) {
-                    migrations.push(SqlxMigration::new(
-                        migration.version,
-                        migration.description.into(),
-                        migration.kind.into(),
-                        migration.sql.into(),
-                    ));
-                }
-            }
+            let mut migrations = self.0;
+            // the migrator expects the migrations in ascending order
+            migrations.sort_by_key(|migration| migration.version);
             Ok(migrations)
         })
     }
 }
 
+/// A migration as listed by `migration_status`.
+#[derive(Serialize)]
+struct MigrationInfo {
+    version: i64,
+    /// `None` for applied migrations that aren't registered anymore.
+    description: Option<String>,
+    /// The hex encoded checksum of the up migration, as stored in the database for applied migrations.
+    checksum: String,
+}
+
+#[derive(Serialize)]
+struct MigrationStatus {
+    applied: Vec<MigrationInfo>,
+    pending: Vec<MigrationInfo>,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Reverts the applied migrations newer than `target`, failing before any of them is reverted
+/// if one has no down migration.
+async fn revert_migrations(pool: &DbPool, migrations: MigrationList, target: i64) -> Result<()> {
+    for applied in pool.applied_migrations().await? {
+        if applied.version > target && !migrations.contains(applied.version, MigrationKind::Down) {
+            return Err(Error::IrreversibleMigration(applied.version));
+        }
+    }
+    let migrator = Migrator::new(migrations).await?;
+    pool.undo(&migrator, target).await
+}
+
 #[command]
 async fn load<R: Runtime>(
-    #[allow(unused_variables)] app: AppHandle<R>,
+    app: AppHandle<R>,
     db_instances: State<'_, DbInstances>,
     migrations: State<'_, Migrations>,
     db: String,
 ) -> Result<String> {
-    #[cfg(feature = "sqlite")]
-    let fqdb = path_mapper(app_path(&app), &db);
-    #[cfg(not(feature = "sqlite"))]
-    let fqdb = db.clone();
-
-    #[cfg(feature = "sqlite")]
-    create_dir_all(app_path(&app)).expect("Problem creating App directory!");
+    let pool = DbPool::connect(&db, &app).await?;
 
-    if !Db::database_exists(&fqdb).await.unwrap_or(false) {
-        Db::create_database(&fqdb).await?;
-    }
-    let pool = Pool::connect(&fqdb).await?;
-
-    if let Some(migrations) = migrations.0.lock().await.remove(&db) {
+    let migrations = migrations.0.lock().await.get(&db).cloned();
+    if let Some(migrations) = migrations {
         let migrator = Migrator::new(migrations).await?;
-        migrator.run(&pool).await?;
+        pool.migrate(&migrator).await?;
     }
 
     db_instances.0.lock().await.insert(db.clone(), pool);
@@ -177,8 +275,16 @@ async fn load<R: Runtime>(
 /// Allows the database connection(s) to be closed; if no database
 /// name is passed in then _all_ database connection pools will be
 /// shut down.
+///
+/// Transactions that are still open on the closed databases are rolled back
+/// and their cursors are closed.
 #[command]
-async fn close(db_instances: State<'_, DbInstances>, db: Option<String>) -> Result<bool> {
+async fn close(
+    db_instances: State<'_, DbInstances>,
+    transactions: State<'_, DbTransactions>,
+    cursors: State<'_, DbCursors>,
+    db: Option<String>,
+) -> Result<bool> {
     let mut instances = db_instances.0.lock().await;
 
     let pools = if let Some(db) = db {
@@ -187,6 +293,9 @@ async fn close(db_instances: State<'_, DbInstances>, db: Option<String>) -> Resu
         instances.keys().cloned().collect()
     };
 
+    transactions.rollback_dbs(&pools).await;
+    cursors.close_dbs(&pools).await;
+
     for pool in pools {
         let db = instances
             .get_mut(&pool) //
@@ -197,122 +306,331 @@ async fn close(db_instances: State<'_, DbInstances>, db: Option<String>) -> Resu
     Ok(true)
 }
 
-/// Execute a command against the database
+/// Starts a transaction on the database, returning its id.
+///
+/// The transaction is rolled back if it's still open when the window is destroyed.
+#[command]
+async fn begin<R: Runtime>(
+    window: Window<R>,
+    db_instances: State<'_, DbInstances>,
+    transactions: State<'_, DbTransactions>,
+    db: String,
+) -> Result<TransactionId> {
+    let instances = db_instances.0.lock().await;
+    let pool = instances
+        .get(&db)
+        .ok_or_else(|| Error::DatabaseNotLoaded(db.clone()))?;
+    let transaction = pool.begin().await?;
+    drop(instances);
+
+    Ok(transactions
+        .insert(db, window.label().to_string(), transaction)
+        .await)
+}
+
+#[command]
+async fn commit(
+    transactions: State<'_, DbTransactions>,
+    transaction_id: TransactionId,
+) -> Result<()> {
+    transactions.remove(transaction_id).await?.commit().await
+}
+
+#[command]
+async fn rollback(
+    transactions: State<'_, DbTransactions>,
+    transaction_id: TransactionId,
+) -> Result<()> {
+    transactions.remove(transaction_id).await?.rollback().await
+}
+
+/// Execute a command against the database, inside of the transaction if one is given
 #[command]
 async fn execute(
     db_instances: State<'_, DbInstances>,
+    transactions: State<'_, DbTransactions>,
     db: String,
     query: String,
-    values: Vec<JsonValue>,
+    values: Vec<Param>,
+    transaction_id: Option<TransactionId>,
 ) -> Result<(u64, LastInsertId)> {
+    if let Some(id) = transaction_id {
+        let transaction = transactions.get(&db, id).await?;
+        let mut transaction = transaction.lock().await;
+        return transaction
+            .as_mut()
+            .ok_or(Error::TransactionNotFound(id))?
+            .execute(&query, values)
+            .await;
+    }
+
     let mut instances = db_instances.0.lock().await;
 
     let db = instances.get_mut(&db).ok_or(Error::DatabaseNotLoaded(db))?;
-    let mut query = sqlx::query(&query);
-    for value in values {
-        if value.is_null() {
-            query = query.bind(None::<JsonValue>);
-        } else if value.is_string() {
-            query = query.bind(value.as_str().unwrap().to_owned())
-        } else {
-            query = query.bind(value);
-        }
-    }
-    let result = query.execute(&*db).await?;
-    #[cfg(feature = "sqlite")]
-    let r = Ok((result.rows_affected(), result.last_insert_rowid()));
-    #[cfg(feature = "mysql")]
-    let r = Ok((result.rows_affected(), result.last_insert_id()));
-    #[cfg(feature = "postgres")]
-    let r = Ok((result.rows_affected(), 0));
-    r
+    db.execute(&query, values).await
 }
 
 #[command]
 async fn select(
     db_instances: State<'_, DbInstances>,
+    transactions: State<'_, DbTransactions>,
     db: String,
     query: String,
-    values: Vec<JsonValue>,
+    values: Vec<Param>,
+    transaction_id: Option<TransactionId>,
 ) -> Result<Vec<HashMap<String, JsonValue>>> {
+    if let Some(id) = transaction_id {
+        let transaction = transactions.get(&db, id).await?;
+        let mut transaction = transaction.lock().await;
+        return transaction
+            .as_mut()
+            .ok_or(Error::TransactionNotFound(id))?
+            .select(&query, values)
+            .await;
+    }
+
     let mut instances = db_instances.0.lock().await;
     let db = instances.get_mut(&db).ok_or(Error::DatabaseNotLoaded(db))?;
-    let mut query = sqlx::query(&query);
-    for value in values {
-        if value.is_null() {
-            query = query.bind(None::<JsonValue>);
-        } else if value.is_string() {
-            query = query.bind(value.as_str().unwrap().to_owned())
-        } else {
-            query = query.bind(value);
-        }
-    }
-    let rows = query.fetch_all(&*db).await?;
-    let mut values = Vec::new();
-    for row in rows {
-        let mut value = HashMap::default();
-        for (i, column) in row.columns().iter().enumerate() {
-            let v = row.try_get_raw(i)?;
+    db.select(&query, values).await
+}
 
-            let v = crate::decode::to_json(v)?;
+/// Opens a cursor over the rows of a SELECT query, returning its id.
+///
+/// The rows are read in batches with `fetch_next` instead of being loaded all at once.
+/// The cursor is closed if it's still open when the window is destroyed.
+#[command]
+async fn select_stream<R: Runtime>(
+    window: Window<R>,
+    db_instances: State<'_, DbInstances>,
+    cursors: State<'_, DbCursors>,
+    db: String,
+    query: String,
+    values: Vec<Param>,
+) -> Result<CursorId> {
+    let pool = db_instances
+        .0
+        .lock()
+        .await
+        .get(&db)
+        .cloned()
+        .ok_or_else(|| Error::DatabaseNotLoaded(db.clone()))?;
+    Ok(cursors
+        .open(db, window.label().to_string(), pool, query, values)
+        .await)
+}
 
-            value.insert(column.name().to_string(), v);
-        }
+/// Returns the next `count` rows of the cursor, fewer once all rows were read.
+#[command]
+async fn fetch_next(
+    cursors: State<'_, DbCursors>,
+    cursor_id: CursorId,
+    count: usize,
+) -> Result<Vec<HashMap<String, JsonValue>>> {
+    cursors.fetch_next(cursor_id, count).await
+}
 
-        values.push(value);
-    }
+#[command]
+async fn close_cursor(cursors: State<'_, DbCursors>, cursor_id: CursorId) -> Result<bool> {
+    Ok(cursors.close(cursor_id).await)
+}
+
+/// Lists the applied and the pending migrations of the database.
+#[command]
+async fn migration_status(
+    db_instances: State<'_, DbInstances>,
+    migrations: State<'_, Migrations>,
+    db: String,
+) -> Result<MigrationStatus> {
+    let migrations = migrations.0.lock().await.get(&db).cloned();
+    let instances = db_instances.0.lock().await;
+    let pool = instances.get(&db).ok_or(Error::DatabaseNotLoaded(db))?;
+    let applied = pool.applied_migrations().await?;
+
+    let migrator = Migrator::new(migrations.unwrap_or_default()).await?;
+    let registered: Vec<_> = migrator
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .collect();
+
+    Ok(MigrationStatus {
+        applied: applied
+            .iter()
+            .map(|applied| MigrationInfo {
+                version: applied.version,
+                description: registered
+                    .iter()
+                    .find(|migration| migration.version == applied.version)
+                    .map(|migration| migration.description.to_string()),
+                checksum: hex(&applied.checksum),
+            })
+            .collect(),
+        pending: registered
+            .iter()
+            .filter(|migration| !applied.iter().any(|a| a.version == migration.version))
+            .map(|migration| MigrationInfo {
+                version: migration.version,
+                description: Some(migration.description.to_string()),
+                checksum: hex(&migration.checksum),
+            })
+            .collect(),
+    })
+}
+
+/// Applies or reverts migrations until `version` is the latest applied migration.
+#[command]
+async fn migrate_to(
+    db_instances: State<'_, DbInstances>,
+    migrations: State<'_, Migrations>,
+    db: String,
+    version: i64,
+) -> Result<()> {
+    let migrations = migrations
+        .0
+        .lock()
+        .await
+        .get(&db)
+        .cloned()
+        .unwrap_or_default();
+    let instances = db_instances.0.lock().await;
+    let pool = instances.get(&db).ok_or(Error::DatabaseNotLoaded(db))?;
+
+    revert_migrations(pool, migrations.clone(), version).await?;
+    let migrator = Migrator::new(migrations.up_to(version)).await?;
+    pool.migrate(&migrator).await
+}
 
-    Ok(values)
+/// Reverts the last `count` applied migrations.
+#[command]
+async fn undo_migrations(
+    db_instances: State<'_, DbInstances>,
+    migrations: State<'_, Migrations>,
+    db: String,
+    count: usize,
+) -> Result<()> {
+    let migrations = migrations
+        .0
+        .lock()
+        .await
+        .get(&db)
+        .cloned()
+        .unwrap_or_default();
+    let instances = db_instances.0.lock().await;
+    let pool = instances.get(&db).ok_or(Error::DatabaseNotLoaded(db))?;
+
+    if count == 0 {
+        return Ok(());
+    }
+    let applied = pool.applied_migrations().await?;
+    if count > applied.len() {
+        return Err(Error::NotEnoughMigrations {
+            count,
+            applied: applied.len(),
+        });
+    }
+    // every migration newer than the one before the last `count` is reverted
+    let target = match (applied.len() - count).checked_sub(1) {
+        Some(index) => applied[index].version,
+        None => i64::MIN,
+    };
+    revert_migrations(pool, migrations, target).await
 }
 
 /// Tauri SQL plugin builder.
 #[derive(Default)]
 pub struct Builder {
     migrations: Option<HashMap<String, MigrationList>>,
+    /// The first error registering migrations, which fails the plugin setup.
+    error: Option<Error>,
 }
 
 impl Builder {
     /// Add migrations to a database.
+    ///
+    /// Migrations added by several calls for the same database are combined and sorted by version.
+    /// Adding a version twice or a down migration without an up migration is an error, which is reported
+    /// when the plugin is set up as the app starts, not by [`Self::build`].
     #[must_use]
-    pub fn add_migrations(mut self, db_url: &str, migrations: Vec<Migration>) -> Self {
-        self.migrations
-            .get_or_insert(Default::default())
-            .insert(db_url.to_string(), MigrationList(migrations));
+    pub fn add_migrations(self, db_url: &str, migrations: Vec<Migration>) -> Self {
+        self.register(db_url, Ok(migrations.into_iter().map(Into::into).collect()))
+    }
+
+    /// Add the migrations of a directory to a database.
+    ///
+    /// The files are named `<version>_<description>.up.sql` and `<version>_<description>.down.sql`,
+    /// like the migrations of the sqlx CLI. Other files are ignored.
+    /// The directory is read right away, but errors are only reported when the plugin is set up.
+    #[must_use]
+    pub fn add_migrations_dir(self, db_url: &str, dir: impl AsRef<Path>) -> Self {
+        let migrations = migration_files::read_dir(dir.as_ref());
+        self.register(db_url, migrations)
+    }
+
+    /// Add the migrations of a directory embedded with [`include_dir::include_dir`] to a database.
+    ///
+    /// The files are named like for [`Self::add_migrations_dir`].
+    #[cfg(feature = "include_dir")]
+    #[must_use]
+    pub fn add_embedded_migrations(self, db_url: &str, dir: &include_dir::Dir<'static>) -> Self {
+        let migrations = migration_files::read_embedded(dir);
+        self.register(db_url, migrations)
+    }
+
+    fn register(mut self, db_url: &str, migrations: Result<Vec<SqlxMigration>>) -> Self {
+        if self.error.is_none() {
+            let list = self
+                .migrations
+                .get_or_insert(Default::default())
+                .entry(db_url.to_string())
+                .or_default();
+            if let Err(e) = migrations.and_then(|migrations| list.extend(db_url, migrations)) {
+                self.error.replace(e);
+            }
+        }
         self
     }
 
     pub fn build<R: Runtime>(mut self) -> TauriPlugin<R, Option<PluginConfig>> {
         PluginBuilder::new("sql")
-            .invoke_handler(tauri::generate_handler![load, execute, select, close])
+            .invoke_handler(tauri::generate_handler![
+                load,
+                execute,
+                select,
+                close,
+                begin,
+                commit,
+                rollback,
+                select_stream,
+                fetch_next,
+                close_cursor,
+                migration_status,
+                migrate_to,
+                undo_migrations
+            ])
             .setup_with_config(|app, config: Option<PluginConfig>| {
                 let config = config.unwrap_or_default();
 
-                #[cfg(feature = "sqlite")]
-                create_dir_all(app_path(app)).expect("problems creating App directory!");
-
                 tauri::async_runtime::block_on(async move {
+                    if let Some(error) = self.error.take() {
+                        return Err(error.into());
+                    }
+
                     let instances = DbInstances::default();
                     let mut lock = instances.0.lock().await;
                     for db in config.preload {
-                        #[cfg(feature = "sqlite")]
-                        let fqdb = path_mapper(app_path(app), &db);
-                        #[cfg(not(feature = "sqlite"))]
-                        let fqdb = db.clone();
-
-                        if !Db::database_exists(&fqdb).await.unwrap_or(false) {
-                            Db::create_database(&fqdb).await?;
-                        }
-                        let pool = Pool::connect(&fqdb).await?;
+                        let pool = DbPool::connect(&db, app).await?;
 
-                        if let Some(migrations) = self.migrations.as_mut().unwrap().remove(&db) {
+                        let migrations = self.migrations.as_ref().and_then(|m| m.get(&db));
+                        if let Some(migrations) = migrations.cloned() {
                             let migrator = Migrator::new(migrations).await?;
-                            migrator.run(&pool).await?;
+                            pool.migrate(&migrator).await?;
                         }
                         lock.insert(db, pool);
                     }
                     drop(lock);
 
                     app.manage(instances);
+                    app.manage(DbTransactions::default());
+                    app.manage(DbCursors::default());
                     app.manage(Migrations(Mutex::new(
                         self.migrations.take().unwrap_or_default(),
                     )));
@@ -320,9 +638,23 @@ impl Builder {
                     Ok(())
                 })
             })
-            .on_event(|app, event| {
-                if let RunEvent::Exit = event {
+            .on_event(|app, event| match event {
+                RunEvent::WindowEvent {
+                    label,
+                    event: WindowEvent::Destroyed,
+                    ..
+                } => {
+                    let app = app.clone();
+                    let label = label.clone();
+                    tauri::async_runtime::spawn(async move {
+                        app.state::<DbTransactions>().rollback_window(&label).await;
+                        app.state::<DbCursors>().close_window(&label).await;
+                    });
+                }
+                RunEvent::Exit => {
                     tauri::async_runtime::block_on(async move {
+                        app.state::<DbTransactions>().rollback_all().await;
+                        app.state::<DbCursors>().close_all().await;
                         let instances = &*app.state::<DbInstances>();
                         let instances = instances.0.lock().await;
                         for value in instances.values() {
@@ -330,6 +662,7 @@ impl Builder {
                         }
                     });
                 }
+                _ => {}
             })
             .build()
     }
@@ -0,0 +1,113 @@
+// Copyright 2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+use tokio::sync::Mutex;
+
+use crate::{wrapper::DbTransaction, Error};
+
+pub(crate) type TransactionId = u32;
+
+/// A transaction together with the database and the window that opened it.
+struct Entry {
+    db: String,
+    window: String,
+    /// `None` once the transaction was committed or rolled back.
+    transaction: Arc<Mutex<Option<DbTransaction>>>,
+}
+
+/// The open transactions of all windows.
+#[derive(Default)]
+pub(crate) struct DbTransactions {
+    next_id: AtomicU32,
+    transactions: Mutex<HashMap<TransactionId, Entry>>,
+}
+
+impl DbTransactions {
+    pub(crate) async fn insert(
+        &self,
+        db: String,
+        window: String,
+        transaction: DbTransaction,
+    ) -> TransactionId {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.transactions.lock().await.insert(
+            id,
+            Entry {
+                db,
+                window,
+                transaction: Arc::new(Mutex::new(Some(transaction))),
+            },
+        );
+        id
+    }
+
+    /// Returns the transaction `id`, which must have been opened on `db`.
+    pub(crate) async fn get(
+        &self,
+        db: &str,
+        id: TransactionId,
+    ) -> Result<Arc<Mutex<Option<DbTransaction>>>, Error> {
+        match self.transactions.lock().await.get(&id) {
+            Some(entry) if entry.db == db => Ok(entry.transaction.clone()),
+            _ => Err(Error::TransactionNotFound(id)),
+        }
+    }
+
+    /// Removes the transaction `id`, waiting for the queries running inside of it to finish.
+    pub(crate) async fn remove(&self, id: TransactionId) -> Result<DbTransaction, Error> {
+        let entry = self
+            .transactions
+            .lock()
+            .await
+            .remove(&id)
+            .ok_or(Error::TransactionNotFound(id))?;
+        let transaction = entry.transaction.lock().await.take();
+        transaction.ok_or(Error::TransactionNotFound(id))
+    }
+
+    /// Rolls back all transactions opened on one of the `dbs`, e.g. before closing their pools.
+    pub(crate) async fn rollback_dbs(&self, dbs: &[String]) {
+        self.rollback_where(|entry| dbs.contains(&entry.db)).await
+    }
+
+    /// Rolls back all transactions opened by a window, e.g. once it's destroyed.
+    pub(crate) async fn rollback_window(&self, window: &str) {
+        self.rollback_where(|entry| entry.window == window).await
+    }
+
+    pub(crate) async fn rollback_all(&self) {
+        self.rollback_where(|_| true).await
+    }
+
+    async fn rollback_where(&self, filter: impl Fn(&Entry) -> bool) {
+        let entries = {
+            let mut transactions = self.transactions.lock().await;
+            let ids: Vec<TransactionId> = transactions
+                .iter()
+                .filter(|(_, entry)| filter(entry))
+                .map(|(id, _)| *id)
+                .collect();
+            ids.into_iter()
+                .filter_map(|id| transactions.remove(&id))
+                .collect::<Vec<_>>()
+        };
+
+        for entry in entries {
+            let transaction = entry.transaction.lock().await.take();
+            if let Some(transaction) = transaction {
+                if let Err(e) = transaction.rollback().await {
+                    log::error!("failed to roll back transaction on {}: {e}", entry.db);
+                }
+            }
+        }
+    }
+}
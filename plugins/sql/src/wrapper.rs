@@ -0,0 +1,393 @@
+// Copyright 2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::collections::HashMap;
+
+use futures_util::{stream::BoxStream, StreamExt};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use sqlx::{
+    database::{HasArguments, HasValueRef},
+    migrate::{AppliedMigration, Migrate, MigrateDatabase, Migrator},
+    query::Query,
+    Column, ColumnIndex, Database, Encode, Pool, Row, Transaction, Type,
+};
+#[cfg(feature = "mysql")]
+use sqlx::{MySql, MySqlPool};
+#[cfg(feature = "postgres")]
+use sqlx::{PgPool, Postgres};
+#[cfg(feature = "sqlite")]
+use sqlx::{Sqlite, SqlitePool};
+use tauri::{AppHandle, Runtime};
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time};
+
+#[cfg(feature = "sqlite")]
+use std::fs::create_dir_all;
+
+use crate::{decode, param::Param, Error};
+
+/// The id of the last inserted row, whose type depends on the database driver.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub(crate) enum LastInsertId {
+    #[cfg(feature = "sqlite")]
+    Sqlite(i64),
+    #[cfg(feature = "mysql")]
+    MySql(u64),
+    /// Postgres doesn't report the last inserted id, this is always `0`.
+    #[cfg(feature = "postgres")]
+    Postgres(u64),
+}
+
+/// A connection pool to one of the supported databases, chosen by the scheme of the connection string.
+#[derive(Clone)]
+pub(crate) enum DbPool {
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqlitePool),
+    #[cfg(feature = "mysql")]
+    MySql(MySqlPool),
+    #[cfg(feature = "postgres")]
+    Postgres(PgPool),
+}
+
+/// An open transaction on one of the supported databases, holding on to a connection of its pool.
+pub(crate) enum DbTransaction {
+    #[cfg(feature = "sqlite")]
+    Sqlite(Transaction<'static, Sqlite>),
+    #[cfg(feature = "mysql")]
+    MySql(Transaction<'static, MySql>),
+    #[cfg(feature = "postgres")]
+    Postgres(Transaction<'static, Postgres>),
+}
+
+/// Creates the database at `url` if it doesn't exist yet and connects to it.
+async fn connect<DB: Database + MigrateDatabase>(url: &str) -> Result<Pool<DB>, Error> {
+    if !DB::database_exists(url).await.unwrap_or(false) {
+        DB::create_database(url).await?;
+    }
+    Ok(Pool::connect(url).await?)
+}
+
+impl DbPool {
+    /// Connects to the database at `conn_url`, creating it if needed.
+    ///
+    /// Sqlite paths are relative to the app directory.
+    pub(crate) async fn connect<R: Runtime>(
+        conn_url: &str,
+        #[allow(unused_variables)] app: &AppHandle<R>,
+    ) -> Result<Self, Error> {
+        let scheme = conn_url
+            .split_once(':')
+            .map(|(scheme, _)| scheme)
+            .ok_or_else(|| Error::InvalidDbUrl(conn_url.to_string()))?;
+
+        match scheme {
+            #[cfg(feature = "sqlite")]
+            "sqlite" => {
+                create_dir_all(crate::app_path(app)).expect("Problem creating App directory!");
+                let fqdb = crate::path_mapper(crate::app_path(app), conn_url);
+                Ok(Self::Sqlite(connect::<Sqlite>(&fqdb).await?))
+            }
+            #[cfg(feature = "mysql")]
+            "mysql" => Ok(Self::MySql(connect::<MySql>(conn_url).await?)),
+            #[cfg(feature = "postgres")]
+            "postgres" | "postgresql" => Ok(Self::Postgres(connect::<Postgres>(conn_url).await?)),
+            _ => Err(Error::InvalidDbUrl(conn_url.to_string())),
+        }
+    }
+
+    pub(crate) async fn migrate(&self, migrator: &Migrator) -> Result<(), Error> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => migrator.run(pool).await?,
+            #[cfg(feature = "mysql")]
+            Self::MySql(pool) => migrator.run(pool).await?,
+            #[cfg(feature = "postgres")]
+            Self::Postgres(pool) => migrator.run(pool).await?,
+        }
+        Ok(())
+    }
+
+    /// Reverts the applied migrations newer than `target`.
+    pub(crate) async fn undo(&self, migrator: &Migrator, target: i64) -> Result<(), Error> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => migrator.undo(pool, target).await?,
+            #[cfg(feature = "mysql")]
+            Self::MySql(pool) => migrator.undo(pool, target).await?,
+            #[cfg(feature = "postgres")]
+            Self::Postgres(pool) => migrator.undo(pool, target).await?,
+        }
+        Ok(())
+    }
+
+    /// Lists the migrations applied to the database, oldest first.
+    pub(crate) async fn applied_migrations(&self) -> Result<Vec<AppliedMigration>, Error> {
+        let mut applied = match self {
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => list_applied_migrations(&mut *pool.acquire().await?).await?,
+            #[cfg(feature = "mysql")]
+            Self::MySql(pool) => list_applied_migrations(&mut *pool.acquire().await?).await?,
+            #[cfg(feature = "postgres")]
+            Self::Postgres(pool) => list_applied_migrations(&mut *pool.acquire().await?).await?,
+        };
+        applied.sort_by_key(|migration| migration.version);
+        Ok(applied)
+    }
+
+    pub(crate) async fn close(&self) {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => pool.close().await,
+            #[cfg(feature = "mysql")]
+            Self::MySql(pool) => pool.close().await,
+            #[cfg(feature = "postgres")]
+            Self::Postgres(pool) => pool.close().await,
+        }
+    }
+
+    /// Starts a new transaction on a connection of the pool.
+    pub(crate) async fn begin(&self) -> Result<DbTransaction, Error> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => Ok(DbTransaction::Sqlite(pool.begin().await?)),
+            #[cfg(feature = "mysql")]
+            Self::MySql(pool) => Ok(DbTransaction::MySql(pool.begin().await?)),
+            #[cfg(feature = "postgres")]
+            Self::Postgres(pool) => Ok(DbTransaction::Postgres(pool.begin().await?)),
+        }
+    }
+
+    /// Executes `query`, returning the number of affected rows and the last inserted id.
+    pub(crate) async fn execute(
+        &self,
+        query: &str,
+        values: Vec<Param>,
+    ) -> Result<(u64, LastInsertId), Error> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => {
+                let result = bind(sqlx::query(query), values).execute(pool).await?;
+                Ok((
+                    result.rows_affected(),
+                    LastInsertId::Sqlite(result.last_insert_rowid()),
+                ))
+            }
+            #[cfg(feature = "mysql")]
+            Self::MySql(pool) => {
+                let result = bind(sqlx::query(query), values).execute(pool).await?;
+                Ok((
+                    result.rows_affected(),
+                    LastInsertId::MySql(result.last_insert_id()),
+                ))
+            }
+            #[cfg(feature = "postgres")]
+            Self::Postgres(pool) => {
+                let result = bind(sqlx::query(query), values).execute(pool).await?;
+                Ok((result.rows_affected(), LastInsertId::Postgres(0)))
+            }
+        }
+    }
+
+    /// Runs `query`, returning the rows as maps of column names to values.
+    pub(crate) async fn select(
+        &self,
+        query: &str,
+        values: Vec<Param>,
+    ) -> Result<Vec<HashMap<String, JsonValue>>, Error> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => bind(sqlx::query(query), values)
+                .fetch_all(pool)
+                .await?
+                .iter()
+                .map(|row| to_map(row, decode::sqlite::to_json))
+                .collect(),
+            #[cfg(feature = "mysql")]
+            Self::MySql(pool) => bind(sqlx::query(query), values)
+                .fetch_all(pool)
+                .await?
+                .iter()
+                .map(|row| to_map(row, decode::mysql::to_json))
+                .collect(),
+            #[cfg(feature = "postgres")]
+            Self::Postgres(pool) => bind(sqlx::query(query), values)
+                .fetch_all(pool)
+                .await?
+                .iter()
+                .map(|row| to_map(row, decode::postgres::to_json))
+                .collect(),
+        }
+    }
+
+    /// Runs `query`, streaming the rows as maps of column names to values instead of collecting them.
+    pub(crate) fn fetch<'a>(
+        &'a self,
+        query: &'a str,
+        values: Vec<Param>,
+    ) -> BoxStream<'a, Result<HashMap<String, JsonValue>, Error>> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => bind(sqlx::query(query), values)
+                .fetch(pool)
+                .map(|row| to_map(&row?, decode::sqlite::to_json))
+                .boxed(),
+            #[cfg(feature = "mysql")]
+            Self::MySql(pool) => bind(sqlx::query(query), values)
+                .fetch(pool)
+                .map(|row| to_map(&row?, decode::mysql::to_json))
+                .boxed(),
+            #[cfg(feature = "postgres")]
+            Self::Postgres(pool) => bind(sqlx::query(query), values)
+                .fetch(pool)
+                .map(|row| to_map(&row?, decode::postgres::to_json))
+                .boxed(),
+        }
+    }
+}
+
+impl DbTransaction {
+    pub(crate) async fn commit(self) -> Result<(), Error> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(tx) => tx.commit().await?,
+            #[cfg(feature = "mysql")]
+            Self::MySql(tx) => tx.commit().await?,
+            #[cfg(feature = "postgres")]
+            Self::Postgres(tx) => tx.commit().await?,
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn rollback(self) -> Result<(), Error> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(tx) => tx.rollback().await?,
+            #[cfg(feature = "mysql")]
+            Self::MySql(tx) => tx.rollback().await?,
+            #[cfg(feature = "postgres")]
+            Self::Postgres(tx) => tx.rollback().await?,
+        }
+        Ok(())
+    }
+
+    /// Executes `query` inside the transaction, see [`DbPool::execute`].
+    pub(crate) async fn execute(
+        &mut self,
+        query: &str,
+        values: Vec<Param>,
+    ) -> Result<(u64, LastInsertId), Error> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(tx) => {
+                let result = bind(sqlx::query(query), values).execute(&mut **tx).await?;
+                Ok((
+                    result.rows_affected(),
+                    LastInsertId::Sqlite(result.last_insert_rowid()),
+                ))
+            }
+            #[cfg(feature = "mysql")]
+            Self::MySql(tx) => {
+                let result = bind(sqlx::query(query), values).execute(&mut **tx).await?;
+                Ok((
+                    result.rows_affected(),
+                    LastInsertId::MySql(result.last_insert_id()),
+                ))
+            }
+            #[cfg(feature = "postgres")]
+            Self::Postgres(tx) => {
+                let result = bind(sqlx::query(query), values).execute(&mut **tx).await?;
+                Ok((result.rows_affected(), LastInsertId::Postgres(0)))
+            }
+        }
+    }
+
+    /// Runs `query` inside the transaction, see [`DbPool::select`].
+    pub(crate) async fn select(
+        &mut self,
+        query: &str,
+        values: Vec<Param>,
+    ) -> Result<Vec<HashMap<String, JsonValue>>, Error> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(tx) => bind(sqlx::query(query), values)
+                .fetch_all(&mut **tx)
+                .await?
+                .iter()
+                .map(|row| to_map(row, decode::sqlite::to_json))
+                .collect(),
+            #[cfg(feature = "mysql")]
+            Self::MySql(tx) => bind(sqlx::query(query), values)
+                .fetch_all(&mut **tx)
+                .await?
+                .iter()
+                .map(|row| to_map(row, decode::mysql::to_json))
+                .collect(),
+            #[cfg(feature = "postgres")]
+            Self::Postgres(tx) => bind(sqlx::query(query), values)
+                .fetch_all(&mut **tx)
+                .await?
+                .iter()
+                .map(|row| to_map(row, decode::postgres::to_json))
+                .collect(),
+        }
+    }
+}
+
+async fn list_applied_migrations<C: Migrate>(conn: &mut C) -> Result<Vec<AppliedMigration>, Error> {
+    conn.ensure_migrations_table().await?;
+    Ok(conn.list_applied_migrations().await?)
+}
+
+/// Binds the `values` to the parameters of `query`, each as the database type of its [`Param`] variant.
+fn bind<'q, DB: Database>(
+    mut query: Query<'q, DB, <DB as HasArguments<'q>>::Arguments>,
+    values: Vec<Param>,
+) -> Query<'q, DB, <DB as HasArguments<'q>>::Arguments>
+where
+    Option<JsonValue>: Encode<'q, DB> + Type<DB>,
+    JsonValue: Encode<'q, DB> + Type<DB>,
+    String: Encode<'q, DB> + Type<DB>,
+    bool: Encode<'q, DB> + Type<DB>,
+    i64: Encode<'q, DB> + Type<DB>,
+    f64: Encode<'q, DB> + Type<DB>,
+    Vec<u8>: Encode<'q, DB> + Type<DB>,
+    Date: Encode<'q, DB> + Type<DB>,
+    Time: Encode<'q, DB> + Type<DB>,
+    PrimitiveDateTime: Encode<'q, DB> + Type<DB>,
+    OffsetDateTime: Encode<'q, DB> + Type<DB>,
+{
+    for value in values {
+        query = match value {
+            Param::Null => query.bind(None::<JsonValue>),
+            Param::Bool(value) => query.bind(value),
+            Param::Int(value) => query.bind(value),
+            Param::Float(value) => query.bind(value),
+            Param::Text(value) => query.bind(value),
+            Param::Bytes(value) => query.bind(value),
+            Param::Json(value) => query.bind(value),
+            Param::Date(value) => query.bind(value),
+            Param::Time(value) => query.bind(value),
+            Param::DateTime(value) => query.bind(value),
+            Param::Timestamp(value) => query.bind(value),
+        };
+    }
+    query
+}
+
+/// Decodes every column of `row` with `to_json`.
+fn to_map<'r, R: Row>(
+    row: &'r R,
+    to_json: fn(<R::Database as HasValueRef<'r>>::ValueRef) -> Result<JsonValue, Error>,
+) -> Result<HashMap<String, JsonValue>, Error>
+where
+    usize: ColumnIndex<R>,
+{
+    let mut value = HashMap::default();
+    for (i, column) in row.columns().iter().enumerate() {
+        let v = row.try_get_raw(i)?;
+        value.insert(column.name().to_string(), to_json(v)?);
+    }
+    Ok(value)
+}
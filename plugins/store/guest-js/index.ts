@@ -7,10 +7,63 @@ import { listen, UnlistenFn } from "@tauri-apps/api/event";
 
 interface ChangePayload<T> {
   path: string;
+  revision: number;
   key: string;
+  pointer?: string;
   value: T | null;
 }
 
+interface ChangesPayload<T> {
+  path: string;
+  revision: number;
+  changes: Array<{ key: string; pointer?: string; value: T | null }>;
+}
+
+export interface SetOptions {
+  /** Time to live of the key, in milliseconds. */
+  ttl?: number;
+}
+
+/**
+ * An operation applied by {@link Store.batch}.
+ */
+export type BatchOperation =
+  | { op: "set"; key: string; value: unknown }
+  | { op: "delete"; key: string };
+
+/**
+ * An RFC 6902 JSON Patch operation applied by {@link Store.patch}.
+ */
+export type PatchOperation =
+  | { op: "add"; path: string; value: unknown }
+  | { op: "remove"; path: string }
+  | { op: "replace"; path: string; value: unknown }
+  | { op: "move"; from: string; path: string }
+  | { op: "copy"; from: string; path: string }
+  | { op: "test"; path: string; value: unknown };
+
+/**
+ * How {@link Store.import} combines the imported entries with the existing ones.
+ *
+ * - `overwrite`: imported values replace the existing values of the same keys, the default.
+ * - `keepExisting`: only keys missing from the store are imported.
+ * - `deepMerge`: imported objects are merged recursively into the existing ones.
+ */
+export type MergeStrategy = "overwrite" | "keepExisting" | "deepMerge";
+
+interface RecoveredPayload {
+  path: string;
+}
+
+/**
+ * Returns the paths of the stores currently loaded in memory.
+ *
+ * @returns
+ */
+export async function listLoaded(): Promise<string[]> {
+  return await invoke("plugin:store|list_loaded");
+}
+
 /**
  * A key-value store persisted by the backend layer.
  */
@@ -23,15 +76,65 @@ export class Store {
   /**
    * Inserts a key-value pair into the store.
    *
+   * If `options.ttl` is set, the key expires after the given number of milliseconds.
+   * Expired keys are hidden from every getter and reported as changed to `null` within a second of expiring.
+   * Setting the key again without a TTL clears its TTL.
+   *
    * @param key
    * @param value
-   * @returns
+   * @param options
+   * @returns The revision of the store after the change.
    */
-  async set(key: string, value: unknown): Promise<void> {
+  async set(
+    key: string,
+    value: unknown,
+    options?: SetOptions,
+  ): Promise<number> {
     return await invoke("plugin:store|set", {
       path: this.path,
       key,
       value,
+      ttl: options?.ttl,
+    });
+  }
+
+  /**
+   * Inserts a key-value pair only if the store is still at `expectedRevision`.
+   *
+   * Rejects without changing the store if another write happened since, e.g. from another window.
+   *
+   * @example
+   * ```typescript
+   * const revision = await store.revision();
+   * const counter = (await store.get<number>("counter")) ?? 0;
+   * await store.compareAndSet("counter", counter + 1, revision);
+   * ```
+   * @param key
+   * @param value
+   * @param expectedRevision
+   * @returns The revision of the store after the change.
+   */
+  async compareAndSet(
+    key: string,
+    value: unknown,
+    expectedRevision: number,
+  ): Promise<number> {
+    return await invoke("plugin:store|compare_and_set", {
+      path: this.path,
+      key,
+      value,
+      expectedRevision,
+    });
+  }
+
+  /**
+   * Returns the current revision of the store, incremented on every change.
+   *
+   * @returns
+   */
+  async revision(): Promise<number> {
+    return await invoke("plugin:store|revision", {
+      path: this.path,
     });
   }
 
@@ -48,6 +151,21 @@ export class Store {
     });
   }
 
+  /**
+   * Returns the value at a JSON pointer into the store or `null` if there is none.
+   *
+   * The first segment of the pointer is the key, e.g. `/settings/theme` returns the `theme` field of the `settings` key.
+   *
+   * @param pointer
+   * @returns
+   */
+  async getPointer<T>(pointer: string): Promise<T | null> {
+    return await invoke("plugin:store|get_pointer", {
+      path: this.path,
+      pointer,
+    });
+  }
+
   /**
    * Returns `true` if the given `key` exists in the store.
    *
@@ -68,19 +186,24 @@ export class Store {
    * @returns
    */
   async delete(key: string): Promise<boolean> {
-    return await invoke("plugin:store|delete", {
-      path: this.path,
-      key,
-    });
+    const { deleted } = await invoke<{ deleted: boolean; revision: number }>(
+      "plugin:store|delete",
+      {
+        path: this.path,
+        key,
+      },
+    );
+    return deleted;
   }
 
   /**
    * Clears the store, removing all key-value pairs.
    *
    * Note: To clear the storage and reset it to it's `default` value, use `reset` instead.
-   * @returns
+   * A single `store://changes` event lists every removed key.
+   * @returns The revision of the store after the change.
    */
-  async clear(): Promise<void> {
+  async clear(): Promise<number> {
     return await invoke("plugin:store|clear", {
       path: this.path,
     });
@@ -90,9 +213,10 @@ export class Store {
    * Resets the store to it's `default` value.
    *
    * If no default value has been set, this method behaves identical to `clear`.
-   * @returns
+   * A single `store://changes` event lists every key that changed.
+   * @returns The revision of the store after the change.
    */
-  async reset(): Promise<void> {
+  async reset(): Promise<number> {
     return await invoke("plugin:store|reset", {
       path: this.path,
     });
@@ -142,6 +266,118 @@ export class Store {
     });
   }
 
+  /**
+   * Applies several operations to the store at once.
+   *
+   * Listeners are notified once with all changed keys instead of once per operation.
+   *
+   * @example
+   * ```typescript
+   * await store.batch([
+   *   { op: "set", key: "theme", value: "dark" },
+   *   { op: "delete", key: "legacy-theme" },
+   * ]);
+   * ```
+   * @param operations
+   * @returns The revision of the store after the change.
+   */
+  async batch(operations: BatchOperation[]): Promise<number> {
+    return await invoke("plugin:store|batch", {
+      path: this.path,
+      operations,
+    });
+  }
+
+  /**
+   * Applies RFC 6902 JSON Patch operations to the store, treating it as a single JSON object.
+   *
+   * Either every operation is applied or, if one fails, none are.
+   * Listeners receive the JSON `pointer` each operation changed along with the value at that pointer.
+   *
+   * @example
+   * ```typescript
+   * await store.patch([
+   *   { op: "replace", path: "/settings/theme", value: "dark" },
+   *   { op: "remove", path: "/legacy-theme" },
+   * ]);
+   * ```
+   * @param operations
+   * @returns The revision of the store after the change.
+   */
+  async patch(operations: PatchOperation[]): Promise<number> {
+    return await invoke("plugin:store|patch", {
+      path: this.path,
+      operations,
+    });
+  }
+
+  /**
+   * Returns a snapshot of every entry of the store, e.g. to back it up or move it to another store.
+   *
+   * @returns
+   */
+  async export(): Promise<Record<string, unknown>> {
+    return await invoke("plugin:store|export", {
+      path: this.path,
+    });
+  }
+
+  /**
+   * Imports entries, e.g. from {@link Store.export}, combining them with the existing ones according to `strategy`.
+   *
+   * Either every entry is imported and a single change event is emitted, or the store is left unchanged.
+   *
+   * @example
+   * ```typescript
+   * const backup = await store.export();
+   * await store.import(backup, "deepMerge");
+   * ```
+   * @param data
+   * @param strategy
+   * @returns The revision of the store after the change.
+   */
+  async import(
+    data: Record<string, unknown>,
+    strategy?: MergeStrategy,
+  ): Promise<number> {
+    return await invoke("plugin:store|import", {
+      path: this.path,
+      data,
+      strategy,
+    });
+  }
+
+  /**
+   * Reverts the last change, emitting a change event for every key it touched.
+   *
+   * Only stores built with `StoreBuilder::history` on the Rust side record their changes.
+   * @returns Whether there was a change to revert.
+   */
+  async undo(): Promise<boolean> {
+    const { applied } = await invoke<{ applied: boolean; revision: number }>(
+      "plugin:store|undo",
+      {
+        path: this.path,
+      },
+    );
+    return applied;
+  }
+
+  /**
+   * Reapplies the last change reverted with {@link Store.undo}, emitting a change event for every key it touched.
+   *
+   * @returns Whether there was a change to reapply.
+   */
+  async redo(): Promise<boolean> {
+    const { applied } = await invoke<{ applied: boolean; revision: number }>(
+      "plugin:store|redo",
+      {
+        path: this.path,
+      },
+    );
+    return applied;
+  }
+
   /**
    * Attempts to load the on-disk state at the stores `path` into memory.
    *
@@ -169,34 +405,103 @@ export class Store {
     });
   }
 
+  /**
+   * Saves the store and unloads it from memory, it is loaded from disk again the next time it is used.
+   *
+   * Stores registered with the plugin builder and stores with change listeners on the Rust side are never unloaded.
+   * @returns Whether the store was unloaded.
+   */
+  async unload(): Promise<boolean> {
+    return await invoke("plugin:store|unload", {
+      path: this.path,
+    });
+  }
+
   /**
    * Listen to changes on a store key.
+   *
+   * When only a nested value changed, e.g. through {@link Store.patch}, `pointer` is the JSON pointer to it
+   * and `value` is the new value at that pointer.
    * @param key
    * @param cb
    * @returns A promise resolving to a function to unlisten to the event.
    */
   async onKeyChange<T>(
     key: string,
-    cb: (value: T | null) => void,
+    cb: (value: T | null, pointer?: string) => void,
   ): Promise<UnlistenFn> {
-    return await listen<ChangePayload<T>>("store://change", (event) => {
-      if (event.payload.path === this.path && event.payload.key === key) {
-        cb(event.payload.value);
-      }
+    return await this.listenChanges<T>([key], (_key, value, pointer) => {
+      cb(value, pointer);
     });
   }
 
   /**
    * Listen to changes on the store.
+   *
+   * When only a nested value changed, e.g. through {@link Store.patch}, `pointer` is the JSON pointer to it
+   * and `value` is the new value at that pointer.
    * @param cb
    * @returns A promise resolving to a function to unlisten to the event.
    */
   async onChange<T>(
-    cb: (key: string, value: T | null) => void,
+    cb: (key: string, value: T | null, pointer?: string) => void,
+  ): Promise<UnlistenFn> {
+    return await this.listenChanges<T>(null, cb);
+  }
+
+  /**
+   * Subscribes this window to changes of the given keys, or every key if `keys` is `null`.
+   *
+   * The backend only delivers change events to windows that subscribed to them.
+   */
+  private async listenChanges<T>(
+    keys: string[] | null,
+    cb: (key: string, value: T | null, pointer?: string) => void,
   ): Promise<UnlistenFn> {
-    return await listen<ChangePayload<T>>("store://change", (event) => {
+    const matches = (path: string, key: string): boolean =>
+      path === this.path && (keys === null || keys.includes(key));
+
+    const unlistenChange = await listen<ChangePayload<T>>(
+      "store://change",
+      (event) => {
+        if (matches(event.payload.path, event.payload.key)) {
+          cb(event.payload.key, event.payload.value, event.payload.pointer);
+        }
+      },
+    );
+    const unlistenChanges = await listen<ChangesPayload<T>>(
+      "store://changes",
+      (event) => {
+        for (const { key, pointer, value } of event.payload.changes) {
+          if (matches(event.payload.path, key)) {
+            cb(key, value, pointer);
+          }
+        }
+      },
+    );
+    const id = await invoke<number>("plugin:store|subscribe", {
+      path: this.path,
+      keys,
+    });
+
+    return () => {
+      unlistenChange();
+      unlistenChanges();
+      void invoke("plugin:store|unsubscribe", { id });
+    };
+  }
+
+  /**
+   * Listen to the store being recovered from its backup file after the store file failed to load.
+   *
+   * Only stores built with `StoreBuilder::backup(true)` on the Rust side keep a backup file.
+   * @param cb
+   * @returns A promise resolving to a function to unlisten to the event.
+   */
+  async onRecovered(cb: () => void): Promise<UnlistenFn> {
+    return await listen<RecoveredPayload>("store://recovered", (event) => {
       if (event.payload.path === this.path) {
-        cb(event.payload.key, event.payload.value);
+        cb();
       }
     });
   }
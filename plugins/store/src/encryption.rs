@@ -0,0 +1,99 @@
+// Copyright 2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use crate::Error;
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use std::sync::Arc;
+
+/// Magic bytes and format version prepended to encrypted stores.
+const HEADER: &[u8] = b"TSE\x01";
+const NONCE_LEN: usize = 24;
+
+/// Provides the 256-bit key used to encrypt a store.
+///
+/// The key is requested every time the store is loaded or saved,
+/// so it can be fetched lazily from e.g. the OS keychain.
+pub trait KeyProvider: Send + Sync {
+    fn key(&self) -> Result<[u8; 32], Box<dyn std::error::Error + Send + Sync>>;
+}
+
+impl KeyProvider for [u8; 32] {
+    fn key(&self) -> Result<[u8; 32], Box<dyn std::error::Error + Send + Sync>> {
+        Ok(*self)
+    }
+}
+
+impl<F> KeyProvider for F
+where
+    F: Fn() -> Result<[u8; 32], Box<dyn std::error::Error + Send + Sync>> + Send + Sync,
+{
+    fn key(&self) -> Result<[u8; 32], Box<dyn std::error::Error + Send + Sync>> {
+        self()
+    }
+}
+
+/// Returns `true` if `bytes` start with the header of an encrypted store.
+pub(crate) fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(HEADER)
+}
+
+/// Encrypts the serialized store with XChaCha20-Poly1305.
+#[derive(Clone)]
+pub(crate) struct Cipher(Arc<dyn KeyProvider>);
+
+impl Cipher {
+    pub(crate) fn new(key_provider: impl KeyProvider + 'static) -> Self {
+        Self(Arc::new(key_provider))
+    }
+
+    fn cipher(&self) -> Result<XChaCha20Poly1305, Error> {
+        let key = self.0.key().map_err(Error::KeyProvider)?;
+        Ok(XChaCha20Poly1305::new(&key.into()))
+    }
+
+    /// Encrypts `bytes` into `HEADER || nonce || ciphertext`, authenticating the header.
+    pub(crate) fn encrypt(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher()?
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: bytes,
+                    aad: HEADER,
+                },
+            )
+            .map_err(|_| Error::Serialize("failed to encrypt store".into()))?;
+
+        let mut out = Vec::with_capacity(HEADER.len() + NONCE_LEN + ciphertext.len());
+        out.extend_from_slice(HEADER);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    /// Decrypts data produced by [`Cipher::encrypt`].
+    ///
+    /// Fails with [`Error::Decrypt`] if the data isn't an encrypted store,
+    /// was encrypted with another key or was tampered with.
+    pub(crate) fn decrypt(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        if bytes.len() < HEADER.len() + NONCE_LEN || !is_encrypted(bytes) {
+            return Err(Error::Decrypt);
+        }
+        let (nonce, ciphertext) = bytes[HEADER.len()..].split_at(NONCE_LEN);
+
+        self.cipher()?
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: HEADER,
+                },
+            )
+            .map_err(|_| Error::Decrypt)
+    }
+}
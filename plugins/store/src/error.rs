@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: MIT
 
 use serde::{Serialize, Serializer};
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::PoisonError};
 
 /// The error types.
 #[derive(thiserror::Error, Debug)]
@@ -13,20 +13,85 @@ pub enum Error {
     Serialize(Box<dyn std::error::Error + Send + Sync>),
     #[error("Failed to deserialize store. {0}")]
     Deserialize(Box<dyn std::error::Error + Send + Sync>),
+    /// A value doesn't match the type it's expected to have.
+    #[error("Invalid value for key \"{key}\". {source}")]
+    InvalidValue {
+        key: String,
+        source: serde_json::Error,
+    },
+    /// Several values don't match the type they're expected to have, each one an [`Error::InvalidValue`].
+    #[error("Invalid values for {} keys. {}", .0.len(), join(.0))]
+    InvalidValues(Vec<Error>),
+    /// A store migration failed.
+    #[error("Failed to migrate store to version {version}. {source}")]
+    Migration {
+        version: usize,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// The encryption key could not be retrieved from the key provider.
+    #[cfg(feature = "encryption")]
+    #[error("Failed to retrieve the store encryption key. {0}")]
+    KeyProvider(Box<dyn std::error::Error + Send + Sync>),
+    /// The store could not be decrypted, either the key is wrong or the data was tampered with.
+    #[cfg(feature = "encryption")]
+    #[error("Failed to decrypt store. The key is wrong or the data is corrupted.")]
+    Decrypt,
+    /// A JSON Patch could not be applied, the store is left unchanged.
+    #[error("Failed to patch store. {0}")]
+    Patch(Box<dyn std::error::Error + Send + Sync>),
+    /// A compare-and-set write was rejected because the store changed since the expected revision.
+    #[error("Store is at revision {actual} but revision {expected} was expected")]
+    RevisionMismatch { expected: u64, actual: u64 },
+    /// A write was rejected by the validator of the key.
+    #[error("Value for key \"{key}\" was rejected. {source}")]
+    Rejected {
+        key: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// A write would grow the store past its maximum serialized size.
+    #[error("Store would be {size} bytes, exceeding its limit of {limit} bytes")]
+    SizeLimitExceeded { size: usize, limit: usize },
+    /// A write would grow the store past its maximum number of keys.
+    #[error("Store would have {count} keys, exceeding its limit of {limit} keys")]
+    KeyLimitExceeded { count: usize, limit: usize },
     /// JSON error.
     #[error(transparent)]
     Json(#[from] serde_json::Error),
     /// IO error.
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    /// The directory of the store could not be resolved.
+    #[error("Failed to resolve the directory of store \"{0}\"")]
+    BaseDirectory(PathBuf),
+    /// The store file could not be watched.
+    #[cfg(feature = "watch")]
+    #[error("Failed to watch store. {0}")]
+    Watch(#[from] notify::Error),
     /// Store not found
     #[error("Store \"{0}\" not found")]
     NotFound(PathBuf),
+    /// A thread panicked while holding a store lock, the store may be in an inconsistent state.
+    #[error("A store lock was poisoned by a panicking thread")]
+    Poisoned,
     /// Some Tauri API failed
     #[error(transparent)]
     Tauri(#[from] tauri::Error),
 }
 
+fn join(errors: &[Error]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl<T> From<PoisonError<T>> for Error {
+    fn from(_: PoisonError<T>) -> Self {
+        Self::Poisoned
+    }
+}
+
 impl Serialize for Error {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
@@ -0,0 +1,129 @@
+// Copyright 2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Serialization formats for the on-disk state of a [`Store`](crate::Store).
+
+use serde_json::Value as JsonValue;
+use std::{collections::HashMap, sync::Arc};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A format used to persist stores.
+pub trait StoreFormat: Send + Sync {
+    /// Serializes the store contents.
+    fn serialize(&self, cache: &HashMap<String, JsonValue>) -> Result<Vec<u8>, BoxError>;
+
+    /// Deserializes the store contents.
+    fn deserialize(&self, bytes: &[u8]) -> Result<HashMap<String, JsonValue>, BoxError>;
+}
+
+/// Compact JSON, the default format.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+impl StoreFormat for Json {
+    fn serialize(&self, cache: &HashMap<String, JsonValue>) -> Result<Vec<u8>, BoxError> {
+        Ok(serde_json::to_vec(cache)?)
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<HashMap<String, JsonValue>, BoxError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+/// Indented, human readable JSON.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrettyJson;
+
+impl StoreFormat for PrettyJson {
+    fn serialize(&self, cache: &HashMap<String, JsonValue>) -> Result<Vec<u8>, BoxError> {
+        Ok(serde_json::to_vec_pretty(cache)?)
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<HashMap<String, JsonValue>, BoxError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+/// TOML. Note that TOML has no `null`, so stores containing null values can't be saved.
+#[cfg(feature = "toml")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Toml;
+
+#[cfg(feature = "toml")]
+impl StoreFormat for Toml {
+    fn serialize(&self, cache: &HashMap<String, JsonValue>) -> Result<Vec<u8>, BoxError> {
+        Ok(toml::to_string(cache)?.into_bytes())
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<HashMap<String, JsonValue>, BoxError> {
+        Ok(toml::from_str(std::str::from_utf8(bytes)?)?)
+    }
+}
+
+/// YAML.
+#[cfg(feature = "yaml")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Yaml;
+
+#[cfg(feature = "yaml")]
+impl StoreFormat for Yaml {
+    fn serialize(&self, cache: &HashMap<String, JsonValue>) -> Result<Vec<u8>, BoxError> {
+        Ok(serde_yaml::to_string(cache)?.into_bytes())
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<HashMap<String, JsonValue>, BoxError> {
+        Ok(serde_yaml::from_slice(bytes)?)
+    }
+}
+
+/// MessagePack.
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessagePack;
+
+#[cfg(feature = "msgpack")]
+impl StoreFormat for MessagePack {
+    fn serialize(&self, cache: &HashMap<String, JsonValue>) -> Result<Vec<u8>, BoxError> {
+        Ok(rmp_serde::to_vec_named(cache)?)
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<HashMap<String, JsonValue>, BoxError> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+}
+
+/// CBOR.
+#[cfg(feature = "cbor")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl StoreFormat for Cbor {
+    fn serialize(&self, cache: &HashMap<String, JsonValue>) -> Result<Vec<u8>, BoxError> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(cache, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<HashMap<String, JsonValue>, BoxError> {
+        Ok(ciborium::from_reader(bytes)?)
+    }
+}
+
+/// Returns the format matching a file extension, if its feature is enabled.
+pub fn from_extension(extension: &str) -> Option<Arc<dyn StoreFormat>> {
+    match extension.to_ascii_lowercase().as_str() {
+        "json" => Some(Arc::new(Json)),
+        #[cfg(feature = "toml")]
+        "toml" => Some(Arc::new(Toml)),
+        #[cfg(feature = "yaml")]
+        "yaml" | "yml" => Some(Arc::new(Yaml)),
+        #[cfg(feature = "msgpack")]
+        "msgpack" | "mpk" => Some(Arc::new(MessagePack)),
+        #[cfg(feature = "cbor")]
+        "cbor" => Some(Arc::new(Cbor)),
+        _ => None,
+    }
+}
@@ -0,0 +1,66 @@
+// Copyright 2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use serde_json::Value as JsonValue;
+use std::collections::VecDeque;
+
+/// The values the keys touched by a change had before it, `None` for keys that didn't exist,
+/// along with their expiration timestamps, `None` for keys without a TTL.
+///
+/// Restoring these values reverts the change.
+pub(crate) type HistoryEntry = Vec<(String, Option<JsonValue>, Option<u64>)>;
+
+/// A bounded undo and redo history of the changes made to a store.
+#[derive(Clone)]
+pub(crate) struct History {
+    limit: usize,
+    undo: VecDeque<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+}
+
+impl History {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Records a new change, discarding the changes that were undone before it.
+    pub(crate) fn record(&mut self, entry: HistoryEntry) {
+        if entry.is_empty() || self.limit == 0 {
+            return;
+        }
+        self.redo.clear();
+        self.push_undo(entry);
+    }
+
+    pub(crate) fn pop_undo(&mut self) -> Option<HistoryEntry> {
+        self.undo.pop_back()
+    }
+
+    pub(crate) fn pop_redo(&mut self) -> Option<HistoryEntry> {
+        self.redo.pop()
+    }
+
+    pub(crate) fn push_undo(&mut self, entry: HistoryEntry) {
+        self.undo.push_back(entry);
+        if self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+    }
+
+    pub(crate) fn push_redo(&mut self, entry: HistoryEntry) {
+        self.redo.push(entry);
+    }
+
+    pub(crate) fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub(crate) fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
@@ -2,51 +2,133 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
+#[cfg(feature = "encryption")]
+pub use encryption::KeyProvider;
 pub use error::Error;
+pub use format::StoreFormat;
+pub use json_patch::Patch as JsonPatch;
 use log::warn;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 pub use serde_json::Value as JsonValue;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
-    sync::Mutex,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
 };
-pub use store::{Store, StoreBuilder};
+pub use store::{BaseDirectory, MergeStrategy, MigrationFn, Store, StoreBuilder, Transaction};
+use subscription::{SubscriptionId, Subscriptions};
 use tauri::{
     plugin::{self, TauriPlugin},
-    AppHandle, Manager, RunEvent, Runtime, State,
+    AppHandle, Manager, RunEvent, Runtime, State, Window, WindowEvent,
 };
 
+#[cfg(feature = "encryption")]
+mod encryption;
 mod error;
+pub mod format;
+mod history;
 mod store;
+mod subscription;
+#[cfg(feature = "watch")]
+mod watch;
 
 #[derive(Serialize, Clone)]
 struct ChangePayload<'a> {
     path: &'a Path,
+    revision: u64,
     key: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pointer: Option<&'a str>,
     value: &'a JsonValue,
 }
 
+#[derive(Serialize, Clone, Copy)]
+struct Change<'a> {
+    key: &'a str,
+    /// The JSON pointer to the nested value that changed, if not the whole value of `key`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pointer: Option<&'a str>,
+    value: &'a JsonValue,
+}
+
+#[derive(Serialize, Clone)]
+struct ChangesPayload<'a> {
+    path: &'a Path,
+    revision: u64,
+    changes: Vec<Change<'a>>,
+}
+
+#[derive(Serialize, Clone)]
+struct RecoveredPayload<'a> {
+    path: &'a Path,
+}
+
+/// How often the expired keys of the loaded stores are purged.
+const PURGE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A store loaded by the plugin.
+///
+/// `None` while the store is loaded from disk and once it was unloaded, so commands that were
+/// waiting for its lock look the store up again instead of using a store that isn't loaded.
+type StoreLock<R> = Arc<RwLock<Option<Store<R>>>>;
+
+/// The stores loaded by the plugin.
+///
+/// Every store has its own lock, so stores can be used concurrently. The map of stores is only
+/// locked to look up, insert or remove a store, stores are loaded and saved with just their own lock.
 #[derive(Default)]
 pub struct StoreCollection<R: Runtime> {
-    stores: Mutex<HashMap<PathBuf, Store<R>>>,
+    stores: RwLock<HashMap<PathBuf, StoreLock<R>>>,
+    /// When each store was last accessed through [`with_store`].
+    last_used: Mutex<HashMap<PathBuf, Instant>>,
+    /// The highest revision of any unloaded store, added to the revision of loaded stores
+    /// so the revision of a store keeps increasing when it is loaded again.
+    unloaded_revision: AtomicU64,
+    /// Stores registered with the plugin builder, they are never unloaded.
+    registered: HashSet<PathBuf>,
     frozen: bool,
+    format_by_extension: bool,
+    max_loaded: Option<usize>,
+    /// Whether the thread purging expired keys was started, which happens once a store has keys with a TTL.
+    purging: AtomicBool,
 }
 
-pub fn with_store<R: Runtime, T, F: FnOnce(&mut Store<R>) -> Result<T, Error>>(
-    app: AppHandle<R>,
-    collection: State<'_, StoreCollection<R>>,
-    path: impl AsRef<Path>,
-    f: F,
-) -> Result<T, Error> {
-    let mut stores = collection.stores.lock().expect("mutex poisoned");
+impl<R: Runtime> StoreCollection<R> {
+    /// Returns the lock of the store at `path` if it is loaded.
+    pub(crate) fn loaded(&self, path: &Path) -> Result<Option<StoreLock<R>>, Error> {
+        Ok(self.stores.read()?.get(path).cloned())
+    }
 
-    let path = path.as_ref();
-    if !stores.contains_key(path) {
-        if collection.frozen {
-            return Err(Error::NotFound(path.to_path_buf()));
+    /// Loads the store at `path` from disk, unless it was loaded in the meantime.
+    fn load_store(&self, app: AppHandle<R>, path: &Path) -> Result<(), Error> {
+        let lock: StoreLock<R> = Arc::new(RwLock::new(None));
+        // commands using the store wait for this lock until it is loaded
+        let mut loading = lock.write()?;
+        let revision = {
+            let mut stores = self.stores.write()?;
+            if stores.contains_key(path) {
+                return Ok(());
+            }
+            if self.frozen {
+                return Err(Error::NotFound(path.to_path_buf()));
+            }
+            self.last_used
+                .lock()?
+                .insert(path.to_path_buf(), Instant::now());
+            stores.insert(path.to_path_buf(), lock.clone());
+            self.unloaded_revision.load(Ordering::SeqCst)
+        };
+
+        let mut builder = StoreBuilder::new(app.clone(), path.to_path_buf());
+        if self.format_by_extension {
+            builder = builder.format_from_extension();
         }
-        let mut store = StoreBuilder::new(app, path.to_path_buf()).build();
+        let mut store = builder.build();
         // ignore loading errors, just use the default
         if let Err(err) = store.load() {
             warn!(
@@ -54,12 +136,228 @@ pub fn with_store<R: Runtime, T, F: FnOnce(&mut Store<R>) -> Result<T, Error>>(
                 path, err
             );
         }
-        stores.insert(path.to_path_buf(), store);
+        store.revision += revision;
+        if store.has_ttl() {
+            self.start_purging(&app);
+        }
+        *loading = Some(store);
+        drop(loading);
+
+        self.evict_least_recently_used(path)
+    }
+
+    /// Saves the store at `path` if it is loaded.
+    pub(crate) fn save_store(&self, path: &Path) -> Result<(), Error> {
+        if let Some(lock) = self.loaded(path)? {
+            if let Some(store) = lock.read()?.as_ref() {
+                store.save()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Saves and unloads the store at `path`.
+    ///
+    /// Returns `false` if the store isn't loaded, was registered with the plugin builder
+    /// or has Rust change listeners, which would be lost.
+    fn unload(&self, path: &Path) -> Result<bool, Error> {
+        if self.registered.contains(path) {
+            return Ok(false);
+        }
+        let lock = match self.loaded(path)? {
+            Some(lock) => lock,
+            None => return Ok(false),
+        };
+
+        // holding the store lock keeps the store from being changed or loaded again until it is removed
+        let mut store = lock.write()?;
+        let revision = match store.as_ref() {
+            Some(store) if store.has_listeners() => return Ok(false),
+            Some(store) => {
+                store.save()?;
+                store.revision
+            }
+            None => return Ok(false),
+        };
+
+        {
+            let mut stores = self.stores.write()?;
+            stores.remove(path);
+            self.unloaded_revision.fetch_max(revision, Ordering::SeqCst);
+            self.last_used.lock()?.remove(path);
+        }
+        *store = None;
+        Ok(true)
+    }
+
+    /// Unloads the least recently used stores until at most `max_loaded` stores opened from the frontend remain.
+    ///
+    /// The store at `current` is never unloaded, it is about to be used.
+    fn evict_least_recently_used(&self, current: &Path) -> Result<(), Error> {
+        let max_loaded = match self.max_loaded {
+            Some(max_loaded) => max_loaded,
+            None => return Ok(()),
+        };
+
+        // stores that can't be unloaded, e.g. because they have Rust change listeners
+        let mut kept = HashSet::new();
+        loop {
+            let path = {
+                let stores = self.stores.read()?;
+                let loaded = stores
+                    .keys()
+                    .filter(|path| !self.registered.contains(*path))
+                    .count();
+                if loaded <= max_loaded {
+                    return Ok(());
+                }
+
+                let last_used = self.last_used.lock()?;
+                // stores that were never accessed sort first
+                stores
+                    .keys()
+                    .filter(|path| {
+                        *path != current
+                            && !self.registered.contains(*path)
+                            && !kept.contains(*path)
+                    })
+                    .min_by_key(|path| last_used.get(*path).copied())
+                    .cloned()
+            };
+            let path = match path {
+                Some(path) => path,
+                None => return Ok(()),
+            };
+            match self.unload(&path) {
+                Ok(true) => {}
+                Ok(false) => {
+                    kept.insert(path);
+                }
+                Err(err) => {
+                    warn!(
+                        "Failed to save store {:?} before unloading it: {}",
+                        path, err
+                    );
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Unloads the stores that weren't accessed for at least `timeout`.
+    fn evict_idle(&self, timeout: Duration) -> Result<(), Error> {
+        let idle: Vec<PathBuf> = self
+            .last_used
+            .lock()?
+            .iter()
+            .filter(|(_, last_used)| last_used.elapsed() >= timeout)
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in idle {
+            if let Err(err) = self.unload(&path) {
+                warn!(
+                    "Failed to save store {:?} before unloading it: {}",
+                    path, err
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Purges the expired keys of the loaded stores every [`PURGE_INTERVAL`] from now on,
+    /// unless that already started.
+    fn start_purging(&self, app: &AppHandle<R>) {
+        if self.purging.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let app = app.clone();
+        thread::spawn(move || loop {
+            thread::sleep(PURGE_INTERVAL);
+            if let Err(err) = app.state::<StoreCollection<R>>().purge_expired() {
+                warn!("Failed to purge expired keys: {}", err);
+            }
+        });
+    }
+
+    /// Purges the expired keys of every loaded store, see [`Store::purge_expired`].
+    fn purge_expired(&self) -> Result<(), Error> {
+        let stores: Vec<(PathBuf, StoreLock<R>)> = self
+            .stores
+            .read()?
+            .iter()
+            .map(|(path, lock)| (path.clone(), lock.clone()))
+            .collect();
+
+        for (path, lock) in stores {
+            // only lock the store for writing if there is something to purge
+            if !matches!(lock.read()?.as_ref(), Some(store) if store.has_expired()) {
+                continue;
+            }
+            if let Some(store) = lock.write()?.as_mut() {
+                if let Err(err) = store.purge_expired() {
+                    warn!("Failed to purge expired keys of store {:?}: {}", path, err);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the lock of the store at `path`, loading the store first if needed.
+    ///
+    /// The map of stores isn't locked anymore once this returns, so the store may be unloaded before
+    /// its lock is acquired, which callers detect by finding `None` in it.
+    fn store_lock(&self, app: AppHandle<R>, path: &Path) -> Result<StoreLock<R>, Error> {
+        loop {
+            if let Some(lock) = self.loaded(path)? {
+                self.last_used
+                    .lock()?
+                    .insert(path.to_path_buf(), Instant::now());
+                return Ok(lock);
+            }
+            self.load_store(app.clone(), path)?;
+        }
     }
+}
 
-    f(stores
-        .get_mut(path)
-        .expect("failed to retrieve store. This is a bug!"))
+pub fn with_store<R: Runtime, T, F: FnOnce(&mut Store<R>) -> Result<T, Error>>(
+    app: AppHandle<R>,
+    collection: State<'_, StoreCollection<R>>,
+    path: impl AsRef<Path>,
+    f: F,
+) -> Result<T, Error> {
+    loop {
+        let lock = collection.store_lock(app.clone(), path.as_ref())?;
+        let mut store = lock.write()?;
+        // otherwise the store was unloaded while waiting for its lock
+        if let Some(store) = store.as_mut() {
+            store.purge_expired()?;
+            let result = f(store);
+            if store.has_ttl() {
+                collection.start_purging(&app);
+            }
+            return result;
+        }
+    }
+}
+
+/// Like [`with_store`], but only takes a read lock so the store can be read concurrently.
+///
+/// Expired keys are hidden by the accessors but not purged.
+fn with_store_read<R: Runtime, T, F: FnOnce(&Store<R>) -> Result<T, Error>>(
+    app: AppHandle<R>,
+    collection: State<'_, StoreCollection<R>>,
+    path: impl AsRef<Path>,
+    f: F,
+) -> Result<T, Error> {
+    loop {
+        let lock = collection.store_lock(app.clone(), path.as_ref())?;
+        let store = lock.read()?;
+        // otherwise the store was unloaded while waiting for its lock
+        if let Some(store) = store.as_ref() {
+            return f(store);
+        }
+    }
 }
 
 #[tauri::command]
@@ -69,8 +367,39 @@ async fn set<R: Runtime>(
     path: PathBuf,
     key: String,
     value: JsonValue,
-) -> Result<(), Error> {
-    with_store(app, stores, path, |store| store.insert(key, value))
+    ttl: Option<u64>,
+) -> Result<u64, Error> {
+    with_store(app, stores, path, |store| {
+        match ttl {
+            Some(ttl) => store.insert_with_ttl(key, value, Duration::from_millis(ttl))?,
+            None => store.insert(key, value)?,
+        }
+        Ok(store.revision())
+    })
+}
+
+/// Sets `key` only if the store at `path` is still at `expected_revision`, returning the new revision.
+#[tauri::command]
+async fn compare_and_set<R: Runtime>(
+    app: AppHandle<R>,
+    stores: State<'_, StoreCollection<R>>,
+    path: PathBuf,
+    key: String,
+    value: JsonValue,
+    expected_revision: u64,
+) -> Result<u64, Error> {
+    with_store(app, stores, path, |store| {
+        store.compare_and_set(expected_revision, key, value)
+    })
+}
+
+#[tauri::command]
+async fn revision<R: Runtime>(
+    app: AppHandle<R>,
+    stores: State<'_, StoreCollection<R>>,
+    path: PathBuf,
+) -> Result<u64, Error> {
+    with_store_read(app, stores, path, |store| Ok(store.revision()))
 }
 
 #[tauri::command]
@@ -80,7 +409,20 @@ async fn get<R: Runtime>(
     path: PathBuf,
     key: String,
 ) -> Result<Option<JsonValue>, Error> {
-    with_store(app, stores, path, |store| Ok(store.get(key).cloned()))
+    with_store_read(app, stores, path, |store| Ok(store.get(key).cloned()))
+}
+
+/// Returns the value at a JSON pointer into the store at `path`, e.g. `/settings/theme`.
+#[tauri::command]
+async fn get_pointer<R: Runtime>(
+    app: AppHandle<R>,
+    stores: State<'_, StoreCollection<R>>,
+    path: PathBuf,
+    pointer: String,
+) -> Result<Option<JsonValue>, Error> {
+    with_store_read(app, stores, path, |store| {
+        Ok(store.get_pointer(pointer).cloned())
+    })
 }
 
 #[tauri::command]
@@ -90,7 +432,13 @@ async fn has<R: Runtime>(
     path: PathBuf,
     key: String,
 ) -> Result<bool, Error> {
-    with_store(app, stores, path, |store| Ok(store.has(key)))
+    with_store_read(app, stores, path, |store| Ok(store.has(key)))
+}
+
+#[derive(Serialize)]
+struct DeleteResponse {
+    deleted: bool,
+    revision: u64,
 }
 
 #[tauri::command]
@@ -99,8 +447,14 @@ async fn delete<R: Runtime>(
     stores: State<'_, StoreCollection<R>>,
     path: PathBuf,
     key: String,
-) -> Result<bool, Error> {
-    with_store(app, stores, path, |store| store.delete(key))
+) -> Result<DeleteResponse, Error> {
+    with_store(app, stores, path, |store| {
+        let deleted = store.delete(key)?;
+        Ok(DeleteResponse {
+            deleted,
+            revision: store.revision(),
+        })
+    })
 }
 
 #[tauri::command]
@@ -108,8 +462,11 @@ async fn clear<R: Runtime>(
     app: AppHandle<R>,
     stores: State<'_, StoreCollection<R>>,
     path: PathBuf,
-) -> Result<(), Error> {
-    with_store(app, stores, path, |store| store.clear())
+) -> Result<u64, Error> {
+    with_store(app, stores, path, |store| {
+        store.clear()?;
+        Ok(store.revision())
+    })
 }
 
 #[tauri::command]
@@ -117,8 +474,11 @@ async fn reset<R: Runtime>(
     app: AppHandle<R>,
     collection: State<'_, StoreCollection<R>>,
     path: PathBuf,
-) -> Result<(), Error> {
-    with_store(app, collection, path, |store| store.reset())
+) -> Result<u64, Error> {
+    with_store(app, collection, path, |store| {
+        store.reset()?;
+        Ok(store.revision())
+    })
 }
 
 #[tauri::command]
@@ -127,7 +487,7 @@ async fn keys<R: Runtime>(
     stores: State<'_, StoreCollection<R>>,
     path: PathBuf,
 ) -> Result<Vec<String>, Error> {
-    with_store(app, stores, path, |store| {
+    with_store_read(app, stores, path, |store| {
         Ok(store.keys().cloned().collect())
     })
 }
@@ -138,7 +498,7 @@ async fn values<R: Runtime>(
     stores: State<'_, StoreCollection<R>>,
     path: PathBuf,
 ) -> Result<Vec<JsonValue>, Error> {
-    with_store(app, stores, path, |store| {
+    with_store_read(app, stores, path, |store| {
         Ok(store.values().cloned().collect())
     })
 }
@@ -149,7 +509,7 @@ async fn entries<R: Runtime>(
     stores: State<'_, StoreCollection<R>>,
     path: PathBuf,
 ) -> Result<Vec<(String, JsonValue)>, Error> {
-    with_store(app, stores, path, |store| {
+    with_store_read(app, stores, path, |store| {
         Ok(store
             .entries()
             .map(|(k, v)| (k.to_owned(), v.to_owned()))
@@ -163,7 +523,154 @@ async fn length<R: Runtime>(
     stores: State<'_, StoreCollection<R>>,
     path: PathBuf,
 ) -> Result<usize, Error> {
-    with_store(app, stores, path, |store| Ok(store.len()))
+    with_store_read(app, stores, path, |store| Ok(store.len()))
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+enum BatchOperation {
+    Set { key: String, value: JsonValue },
+    Delete { key: String },
+}
+
+#[tauri::command]
+async fn batch<R: Runtime>(
+    app: AppHandle<R>,
+    stores: State<'_, StoreCollection<R>>,
+    path: PathBuf,
+    operations: Vec<BatchOperation>,
+) -> Result<u64, Error> {
+    with_store(app, stores, path, |store| {
+        store.transaction(|tx| {
+            for operation in operations {
+                match operation {
+                    BatchOperation::Set { key, value } => tx.insert(key, value),
+                    BatchOperation::Delete { key } => {
+                        tx.delete(key);
+                    }
+                }
+            }
+            Ok(())
+        })?;
+        Ok(store.revision())
+    })
+}
+
+#[tauri::command]
+async fn export<R: Runtime>(
+    app: AppHandle<R>,
+    stores: State<'_, StoreCollection<R>>,
+    path: PathBuf,
+) -> Result<HashMap<String, JsonValue>, Error> {
+    with_store_read(app, stores, path, |store| Ok(store.export()))
+}
+
+/// Imports `data` into the store at `path` with the given merge strategy, returning the new revision.
+#[tauri::command]
+async fn import<R: Runtime>(
+    app: AppHandle<R>,
+    stores: State<'_, StoreCollection<R>>,
+    path: PathBuf,
+    data: HashMap<String, JsonValue>,
+    strategy: Option<MergeStrategy>,
+) -> Result<u64, Error> {
+    with_store(app, stores, path, |store| {
+        store.import(data, strategy.unwrap_or_default())?;
+        Ok(store.revision())
+    })
+}
+
+/// Applies RFC 6902 JSON Patch operations to the store at `path`, atomically.
+#[tauri::command]
+async fn patch<R: Runtime>(
+    app: AppHandle<R>,
+    stores: State<'_, StoreCollection<R>>,
+    path: PathBuf,
+    operations: JsonPatch,
+) -> Result<u64, Error> {
+    with_store(app, stores, path, |store| {
+        store.patch(&operations)?;
+        Ok(store.revision())
+    })
+}
+
+/// Subscribes the calling window to changes of the store at `path`.
+///
+/// If `keys` is given, only changes to these keys are delivered.
+#[tauri::command]
+async fn subscribe<R: Runtime>(
+    window: Window<R>,
+    subscriptions: State<'_, Subscriptions>,
+    path: PathBuf,
+    keys: Option<Vec<String>>,
+) -> Result<SubscriptionId, Error> {
+    subscriptions.subscribe(window.label().to_string(), path, keys)
+}
+
+#[tauri::command]
+async fn unsubscribe(
+    subscriptions: State<'_, Subscriptions>,
+    id: SubscriptionId,
+) -> Result<bool, Error> {
+    subscriptions.unsubscribe(id)
+}
+
+#[derive(Serialize)]
+struct HistoryResponse {
+    applied: bool,
+    revision: u64,
+}
+
+/// Reverts the last change of the store at `path`, returning whether there was one to revert.
+#[tauri::command]
+async fn undo<R: Runtime>(
+    app: AppHandle<R>,
+    stores: State<'_, StoreCollection<R>>,
+    path: PathBuf,
+) -> Result<HistoryResponse, Error> {
+    with_store(app, stores, path, |store| {
+        let applied = store.undo()?;
+        Ok(HistoryResponse {
+            applied,
+            revision: store.revision(),
+        })
+    })
+}
+
+/// Reapplies the last reverted change of the store at `path`, returning whether there was one to reapply.
+#[tauri::command]
+async fn redo<R: Runtime>(
+    app: AppHandle<R>,
+    stores: State<'_, StoreCollection<R>>,
+    path: PathBuf,
+) -> Result<HistoryResponse, Error> {
+    with_store(app, stores, path, |store| {
+        let applied = store.redo()?;
+        Ok(HistoryResponse {
+            applied,
+            revision: store.revision(),
+        })
+    })
+}
+
+/// Saves and unloads the store at `path`, returning `false` if it wasn't loaded.
+///
+/// Stores registered with the plugin builder and stores with Rust change listeners are never unloaded.
+#[tauri::command]
+async fn unload<R: Runtime>(
+    _app: AppHandle<R>,
+    stores: State<'_, StoreCollection<R>>,
+    path: PathBuf,
+) -> Result<bool, Error> {
+    stores.unload(&path)
+}
+
+#[tauri::command]
+async fn list_loaded<R: Runtime>(
+    _app: AppHandle<R>,
+    stores: State<'_, StoreCollection<R>>,
+) -> Result<Vec<PathBuf>, Error> {
+    Ok(stores.stores.read()?.keys().cloned().collect())
 }
 
 #[tauri::command]
@@ -188,6 +695,9 @@ async fn save<R: Runtime>(
 pub struct Builder<R: Runtime> {
     stores: HashMap<PathBuf, Store<R>>,
     frozen: bool,
+    format_by_extension: bool,
+    max_loaded: Option<usize>,
+    idle_timeout: Option<Duration>,
 }
 
 impl<R: Runtime> Default for Builder<R> {
@@ -195,6 +705,9 @@ impl<R: Runtime> Default for Builder<R> {
         Self {
             stores: Default::default(),
             frozen: false,
+            format_by_extension: false,
+            max_loaded: None,
+            idle_timeout: None,
         }
     }
 }
@@ -265,6 +778,72 @@ impl<R: Runtime> Builder<R> {
         self
     }
 
+    /// Picks the format of stores opened from the frontend from their file extension.
+    ///
+    /// See [`StoreBuilder::format_from_extension`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::PluginBuilder;
+    ///
+    /// let builder = PluginBuilder::default().format_by_extension();
+    ///
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn format_by_extension(mut self) -> Self {
+        self.format_by_extension = true;
+        self
+    }
+
+    /// Limits how many stores opened from the frontend stay loaded.
+    ///
+    /// When more stores are opened, the least recently used ones are saved and unloaded.
+    /// They are loaded from disk again the next time they are used.
+    /// Stores registered with [`Builder::store`] don't count towards the limit and are never unloaded.
+    /// Stores with listeners registered with [`Store::on_change`] are never unloaded either.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::PluginBuilder;
+    ///
+    /// let builder = PluginBuilder::default().max_loaded(16);
+    ///
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn max_loaded(mut self, max_loaded: usize) -> Self {
+        self.max_loaded = Some(max_loaded);
+        self
+    }
+
+    /// Saves and unloads stores opened from the frontend once they weren't used for `timeout`.
+    ///
+    /// Idle stores are checked every `timeout`, so a store may stay loaded for up to twice as long.
+    /// Stores registered with [`Builder::store`] and stores with listeners registered with
+    /// [`Store::on_change`] are never unloaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::PluginBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let builder = PluginBuilder::default().idle_timeout(Duration::from_secs(300));
+    ///
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
     /// Builds the plugin.
     ///
     /// # Examples
@@ -284,7 +863,31 @@ impl<R: Runtime> Builder<R> {
     pub fn build(mut self) -> TauriPlugin<R> {
         plugin::Builder::new("store")
             .invoke_handler(tauri::generate_handler![
-                set, get, has, delete, clear, reset, keys, values, length, entries, load, save
+                set,
+                compare_and_set,
+                revision,
+                get,
+                get_pointer,
+                has,
+                delete,
+                clear,
+                reset,
+                keys,
+                values,
+                length,
+                entries,
+                batch,
+                patch,
+                export,
+                import,
+                undo,
+                redo,
+                subscribe,
+                unsubscribe,
+                unload,
+                list_loaded,
+                load,
+                save
             ])
             .setup(move |app_handle| {
                 for (path, store) in self.stores.iter_mut() {
@@ -297,20 +900,78 @@ impl<R: Runtime> Builder<R> {
                     }
                 }
 
+                app_handle.manage(Subscriptions::default());
+                let has_ttl = self.stores.values().any(Store::has_ttl);
                 app_handle.manage(StoreCollection {
-                    stores: Mutex::new(self.stores),
+                    registered: self.stores.keys().cloned().collect(),
+                    stores: RwLock::new(
+                        self.stores
+                            .into_iter()
+                            .map(|(path, store)| (path, Arc::new(RwLock::new(Some(store)))))
+                            .collect(),
+                    ),
+                    last_used: Default::default(),
+                    unloaded_revision: Default::default(),
                     frozen: self.frozen,
+                    format_by_extension: self.format_by_extension,
+                    max_loaded: self.max_loaded,
+                    purging: Default::default(),
                 });
+                if has_ttl {
+                    app_handle
+                        .state::<StoreCollection<R>>()
+                        .start_purging(app_handle);
+                }
+
+                if let Some(timeout) = self.idle_timeout {
+                    let app_handle = app_handle.clone();
+                    thread::spawn(move || loop {
+                        thread::sleep(timeout);
+                        if let Err(err) =
+                            app_handle.state::<StoreCollection<R>>().evict_idle(timeout)
+                        {
+                            warn!("Failed to unload idle stores: {}", err);
+                        }
+                    });
+                }
 
                 Ok(())
             })
             .on_event(|app_handle, event| {
+                if let RunEvent::WindowEvent {
+                    label,
+                    event: WindowEvent::Destroyed,
+                    ..
+                } = event
+                {
+                    if let Err(err) = app_handle
+                        .state::<Subscriptions>()
+                        .unsubscribe_window(label)
+                    {
+                        warn!("Failed to unsubscribe window {}: {}", label, err);
+                    }
+                }
+
                 if let RunEvent::Exit = event {
+                    // this also flushes changes that are still waiting for a debounced auto save
                     let collection = app_handle.state::<StoreCollection<R>>();
 
-                    for store in collection.stores.lock().expect("mutex poisoned").values() {
-                        if let Err(err) = store.save() {
-                            eprintln!("failed to save store {:?} with error {:?}", store.path, err);
+                    let stores: Vec<(PathBuf, StoreLock<R>)> = match collection.stores.read() {
+                        Ok(stores) => stores
+                            .iter()
+                            .map(|(path, lock)| (path.clone(), lock.clone()))
+                            .collect(),
+                        Err(err) => {
+                            eprintln!("failed to save stores with error {:?}", err);
+                            return;
+                        }
+                    };
+                    for (path, lock) in stores {
+                        let saved = lock.read().map_err(Error::from).and_then(|store| {
+                            store.as_ref().map(|store| store.save()).unwrap_or(Ok(()))
+                        });
+                        if let Err(err) = saved {
+                            eprintln!("failed to save store {:?} with error {:?}", path, err);
                         }
                     }
                 }
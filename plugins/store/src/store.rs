@@ -2,31 +2,247 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-use crate::{ChangePayload, Error};
+#[cfg(feature = "encryption")]
+use crate::encryption::{Cipher, KeyProvider};
+use crate::{
+    format::{self, Json, StoreFormat},
+    history::{History, HistoryEntry},
+    subscription, Change, Error, RecoveredPayload, StoreCollection,
+};
+use json_patch::{Patch, PatchOperation};
+use log::warn;
+#[cfg(feature = "watch")]
+use notify::RecommendedWatcher;
+#[cfg(feature = "watch")]
+use notify_debouncer_mini::Debouncer;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use serde_path_to_error::Segment;
 use std::{
-    collections::HashMap,
-    fs::{create_dir_all, read, File},
+    collections::{hash_map::DefaultHasher, HashMap},
+    ffi::OsString,
+    fs::{copy, create_dir_all, read, rename, File},
+    hash::{Hash, Hasher},
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{channel, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use tauri::{AppHandle, Manager, Runtime};
 
-type SerializeFn =
-    fn(&HashMap<String, JsonValue>) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>;
-type DeserializeFn =
-    fn(&[u8]) -> Result<HashMap<String, JsonValue>, Box<dyn std::error::Error + Send + Sync>>;
+type SerializeFn = Arc<
+    dyn Fn(&HashMap<String, JsonValue>) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>
+        + Send
+        + Sync,
+>;
+type DeserializeFn = Arc<
+    dyn Fn(&[u8]) -> Result<HashMap<String, JsonValue>, Box<dyn std::error::Error + Send + Sync>>
+        + Send
+        + Sync,
+>;
+type ValidateFn = fn(&HashMap<String, JsonValue>) -> Result<(), Error>;
+/// A migration upgrading the on-disk contents of a store to the next version.
+pub type MigrationFn = Box<
+    dyn Fn(&mut HashMap<String, JsonValue>) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+        + Send
+        + Sync,
+>;
+
+/// A validator accepting or rejecting the new value of a key.
+type KeyValidatorFn =
+    Arc<dyn Fn(&JsonValue) -> Result<(), Box<dyn std::error::Error + Send + Sync>> + Send + Sync>;
+
+/// A Rust listener notified with the key and new value of changed entries.
+type ChangeListener = Arc<dyn Fn(&str, &JsonValue) + Send + Sync>;
+
+/// Reserved key holding the store version in the on-disk state.
+const VERSION_KEY: &str = "__version__";
+/// Reserved key holding the expiration timestamps of keys inserted with a TTL in the on-disk state.
+const EXPIRATIONS_KEY: &str = "__expirations__";
+
+/// Returns the current time in milliseconds since the unix epoch.
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Checks that the store contents deserialize into `S`, reporting the path of every invalid value.
+///
+/// Keys missing from the store are only reported once all values are valid,
+/// as deserializing stops at the first missing key.
+fn validate_schema<S: DeserializeOwned>(cache: &HashMap<String, JsonValue>) -> Result<(), Error> {
+    let mut object: serde_json::Map<String, JsonValue> = cache.clone().into_iter().collect();
+    let mut errors = Vec::new();
+    loop {
+        let err = match serde_path_to_error::deserialize::<_, S>(JsonValue::Object(object.clone()))
+        {
+            Ok(_) => break,
+            Err(err) => err,
+        };
+        // `None` if the store itself is invalid, e.g. because a key is missing
+        let key = match err.path().iter().next() {
+            Some(Segment::Map { key }) if object.contains_key(key) => Some(key.clone()),
+            _ => None,
+        };
+        let invalid = Error::InvalidValue {
+            key: err.path().to_string(),
+            source: err.into_inner(),
+        };
+        match key {
+            // validate the other keys without the invalid one
+            Some(key) => {
+                object.remove(&key);
+                errors.push(invalid);
+            }
+            // after removing invalid keys, the store is expected to miss them
+            None => {
+                if errors.is_empty() {
+                    errors.push(invalid);
+                }
+                break;
+            }
+        }
+    }
+
+    match errors.len() {
+        0 => Ok(()),
+        1 => Err(errors.remove(0)),
+        _ => Err(Error::InvalidValues(errors)),
+    }
+}
+
+/// The directory a store is persisted in.
+#[derive(Debug, Clone, Default)]
+pub enum BaseDirectory {
+    /// The app data directory, the default.
+    #[default]
+    AppData,
+    /// The app config directory.
+    AppConfig,
+    /// The app cache directory.
+    AppCache,
+    /// A custom directory, should be absolute.
+    Path(PathBuf),
+    /// The store is never read from or written to disk.
+    InMemory,
+}
+
+/// How [`Store::import`] combines the imported entries with the existing ones.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeStrategy {
+    /// Imported values replace the existing values of the same keys, the default.
+    #[default]
+    Overwrite,
+    /// Only keys missing from the store are imported.
+    KeepExisting,
+    /// Imported objects are merged recursively into the existing ones, other imported values replace the existing ones.
+    DeepMerge,
+}
+
+/// Recursively merges `source` into `target`, `source` wins unless both values are objects.
+fn deep_merge(target: &mut JsonValue, source: JsonValue) {
+    match (target, source) {
+        (JsonValue::Object(target), JsonValue::Object(source)) => {
+            for (key, value) in source {
+                match target.get_mut(&key) {
+                    Some(target) => deep_merge(target, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, source) => *target = source,
+    }
+}
+
+/// Resolves the on-disk location of the store at `path`, `None` for in-memory stores.
+fn resolve_path<R: Runtime>(
+    app: &AppHandle<R>,
+    base_dir: &BaseDirectory,
+    path: &Path,
+) -> Result<Option<PathBuf>, Error> {
+    let resolver = app.path_resolver();
+    let dir = match base_dir {
+        BaseDirectory::AppData => resolver.app_data_dir(),
+        BaseDirectory::AppConfig => resolver.app_config_dir(),
+        BaseDirectory::AppCache => resolver.app_cache_dir(),
+        BaseDirectory::Path(dir) => Some(dir.clone()),
+        BaseDirectory::InMemory => return Ok(None),
+    };
+
+    dir.map(|dir| Some(dir.join(path)))
+        .ok_or_else(|| Error::BaseDirectory(path.to_path_buf()))
+}
+
+/// Splits a JSON pointer into the unescaped top-level store key and the pointer inside its value.
+///
+/// Returns `None` if `pointer` doesn't start with a `/`.
+fn split_pointer(pointer: &str) -> Option<(String, &str)> {
+    let pointer = pointer.strip_prefix('/')?;
+    let (key, rest) = match pointer.find('/') {
+        Some(index) => pointer.split_at(index),
+        None => (pointer, ""),
+    };
+    Some((key.replace("~1", "/").replace("~0", "~"), rest))
+}
+
+/// Hashes the contents of a store file, to recognize a state that was already loaded or saved.
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
 
-fn default_serialize(
-    cache: &HashMap<String, JsonValue>,
-) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
-    Ok(serde_json::to_vec(&cache)?)
+/// Returns `path` with `suffix` appended to its file name, e.g. `store.bin` -> `store.bin.tmp`.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(suffix);
+    path.with_file_name(file_name)
 }
 
-fn default_deserialize(
-    bytes: &[u8],
-) -> Result<HashMap<String, JsonValue>, Box<dyn std::error::Error + Send + Sync>> {
-    serde_json::from_slice(bytes).map_err(Into::into)
+/// Spawns a thread that saves the store at `path` once it stopped receiving change notifications for `debounce`.
+///
+/// The store is looked up in the [`StoreCollection`], so only stores managed by the plugin are saved.
+fn spawn_auto_save<R: Runtime>(app: AppHandle<R>, path: PathBuf, debounce: Duration) -> Sender<()> {
+    let (tx, rx) = channel();
+
+    thread::spawn(move || {
+        while rx.recv().is_ok() {
+            // coalesce bursts of changes into a single save
+            loop {
+                match rx.recv_timeout(debounce) {
+                    Ok(()) => continue,
+                    Err(RecvTimeoutError::Timeout) => break,
+                    // the store was dropped, there is nothing left to save
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+
+            let collection = match app.try_state::<StoreCollection<R>>() {
+                Some(collection) => collection,
+                None => {
+                    warn!(
+                        "Failed to auto save store {:?}: the store plugin is not initialized",
+                        path
+                    );
+                    continue;
+                }
+            };
+            if let Err(err) = collection.save_store(&path) {
+                warn!("Failed to auto save store {:?}: {}", path, err);
+            }
+        }
+    });
+
+    tx
 }
 
 /// Builds a [`Store`]
@@ -37,6 +253,21 @@ pub struct StoreBuilder<R: Runtime> {
     cache: HashMap<String, JsonValue>,
     serialize: SerializeFn,
     deserialize: DeserializeFn,
+    backup: bool,
+    auto_save: Option<Duration>,
+    validate: Option<ValidateFn>,
+    migrations: Vec<MigrationFn>,
+    #[cfg(feature = "encryption")]
+    cipher: Option<Cipher>,
+    #[cfg(feature = "encryption")]
+    allow_unencrypted: bool,
+    #[cfg(feature = "watch")]
+    watch_file: bool,
+    base_dir: BaseDirectory,
+    max_size: Option<usize>,
+    max_keys: Option<usize>,
+    validators: HashMap<String, KeyValidatorFn>,
+    history: Option<usize>,
 }
 
 impl<R: Runtime> StoreBuilder<R> {
@@ -58,8 +289,23 @@ impl<R: Runtime> StoreBuilder<R> {
             path,
             defaults: None,
             cache: Default::default(),
-            serialize: default_serialize,
-            deserialize: default_deserialize,
+            serialize: Arc::new(|cache| Json.serialize(cache)),
+            deserialize: Arc::new(|bytes| Json.deserialize(bytes)),
+            backup: false,
+            auto_save: None,
+            validate: None,
+            migrations: Vec::new(),
+            #[cfg(feature = "encryption")]
+            cipher: None,
+            #[cfg(feature = "encryption")]
+            allow_unencrypted: false,
+            #[cfg(feature = "watch")]
+            watch_file: false,
+            base_dir: BaseDirectory::AppData,
+            max_size: None,
+            max_keys: None,
+            validators: HashMap::new(),
+            history: None,
         }
     }
 
@@ -118,8 +364,16 @@ impl<R: Runtime> StoreBuilder<R> {
     ///
     /// # Ok(())
     /// # }
-    pub fn serialize(mut self, serialize: SerializeFn) -> Self {
-        self.serialize = serialize;
+    pub fn serialize<F>(mut self, serialize: F) -> Self
+    where
+        F: Fn(
+                &HashMap<String, JsonValue>,
+            ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.serialize = Arc::new(serialize);
         self
     }
 
@@ -135,8 +389,358 @@ impl<R: Runtime> StoreBuilder<R> {
     ///
     /// # Ok(())
     /// # }
-    pub fn deserialize(mut self, deserialize: DeserializeFn) -> Self {
-        self.deserialize = deserialize;
+    pub fn deserialize<F>(mut self, deserialize: F) -> Self
+    where
+        F: Fn(
+                &[u8],
+            )
+                -> Result<HashMap<String, JsonValue>, Box<dyn std::error::Error + Send + Sync>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.deserialize = Arc::new(deserialize);
+        self
+    }
+
+    /// Defines the format used to serialize and deserialize the store.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::{format::PrettyJson, StoreBuilder};
+    ///
+    /// let builder = StoreBuilder::new("store.json".parse()?)
+    ///   .format(PrettyJson);
+    ///
+    /// # Ok(())
+    /// # }
+    pub fn format(self, format: impl StoreFormat + 'static) -> Self {
+        self.with_format(Arc::new(format))
+    }
+
+    /// Picks the format from the extension of the store path, e.g. `settings.toml` is stored as TOML.
+    ///
+    /// Unknown extensions and formats whose feature is disabled keep the current format.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::StoreBuilder;
+    ///
+    /// let builder = StoreBuilder::new("store.yaml".parse()?)
+    ///   .format_from_extension();
+    ///
+    /// # Ok(())
+    /// # }
+    pub fn format_from_extension(self) -> Self {
+        match self
+            .path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(format::from_extension)
+        {
+            Some(format) => self.with_format(format),
+            None => self,
+        }
+    }
+
+    fn with_format(mut self, format: Arc<dyn StoreFormat>) -> Self {
+        let deserialize_format = format.clone();
+        self.serialize = Arc::new(move |cache| format.serialize(cache));
+        self.deserialize = Arc::new(move |bytes| deserialize_format.deserialize(bytes));
+        self
+    }
+
+    /// Keeps a `.bak` copy of the previous on-disk state on every save.
+    ///
+    /// If the store file fails to deserialize, [`Store::load`] falls back to the backup,
+    /// writes the recovered state back to the store file and emits a `store://recovered` event.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::StoreBuilder;
+    ///
+    /// let builder = StoreBuilder::new("store.json".parse()?)
+    ///   .backup(true);
+    ///
+    /// # Ok(())
+    /// # }
+    pub fn backup(mut self, backup: bool) -> Self {
+        self.backup = backup;
+        self
+    }
+
+    /// Automatically saves the store after it was modified.
+    ///
+    /// Changes are debounced, the store is saved once no modification happened for the given duration.
+    /// Only stores registered with the plugin are saved, pending changes are flushed when the app exits.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::StoreBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let builder = StoreBuilder::new("store.json".parse()?)
+    ///   .auto_save(Duration::from_millis(100));
+    ///
+    /// # Ok(())
+    /// # }
+    pub fn auto_save(mut self, debounce: Duration) -> Self {
+        self.auto_save = Some(debounce);
+        self
+    }
+
+    /// Validates the store contents against the schema `S` when loading it from disk.
+    ///
+    /// [`Store::load`] fails with [`Error::InvalidValue`] naming the offending key, or [`Error::InvalidValues`]
+    /// listing every offending key, instead of loading values that don't match the schema.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::StoreBuilder;
+    ///
+    /// #[derive(serde::Deserialize)]
+    /// struct Settings {
+    ///   theme: String,
+    ///   volume: u8,
+    /// }
+    ///
+    /// let builder = StoreBuilder::new("settings.json".parse()?)
+    ///   .typed::<Settings>();
+    ///
+    /// # Ok(())
+    /// # }
+    pub fn typed<S: DeserializeOwned>(mut self) -> Self {
+        self.validate = Some(validate_schema::<S>);
+        self
+    }
+
+    /// Registers the migrations of the store, in order.
+    ///
+    /// The version of the store is persisted under the reserved `__version__` key.
+    /// When the store is loaded, the migrations it hasn't gone through yet run on the on-disk state
+    /// and the upgraded store is saved back. A store with a higher version than there are migrations,
+    /// written by a newer version of the app, keeps its version when it is saved.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::StoreBuilder;
+    ///
+    /// let builder = StoreBuilder::new("store.json".parse()?)
+    ///   .migrations(vec![
+    ///     Box::new(|store| {
+    ///       store.remove("legacy-key");
+    ///       Ok(())
+    ///     }),
+    ///   ]);
+    ///
+    /// # Ok(())
+    /// # }
+    pub fn migrations(mut self, migrations: Vec<MigrationFn>) -> Self {
+        self.migrations = migrations;
+        self
+    }
+
+    /// Registers a migration to run after the previously registered ones.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::StoreBuilder;
+    ///
+    /// let builder = StoreBuilder::new("store.json".parse()?)
+    ///   .migration(|store| {
+    ///     if let Some(theme) = store.remove("theme") {
+    ///       store.insert("appearance".to_string(), serde_json::json!({ "theme": theme }));
+    ///     }
+    ///     Ok(())
+    ///   });
+    ///
+    /// # Ok(())
+    /// # }
+    pub fn migration<F>(mut self, migration: F) -> Self
+    where
+        F: Fn(
+                &mut HashMap<String, JsonValue>,
+            ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.migrations.push(Box::new(migration));
+        self
+    }
+
+    /// Encrypts the store at rest with XChaCha20-Poly1305.
+    ///
+    /// The key is requested from the `key_provider` whenever the store is loaded or saved.
+    /// Loading a store that isn't encrypted, was tampered with or was encrypted with another key
+    /// fails with [`Error::Decrypt`], see [`StoreBuilder::allow_unencrypted`] to encrypt an existing store.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::StoreBuilder;
+    ///
+    /// let builder = StoreBuilder::new("secrets.bin".parse()?)
+    ///   .encrypted(|| Ok([0u8; 32]));
+    ///
+    /// # Ok(())
+    /// # }
+    #[cfg(feature = "encryption")]
+    pub fn encrypted(mut self, key_provider: impl KeyProvider + 'static) -> Self {
+        self.cipher = Some(Cipher::new(key_provider));
+        self
+    }
+
+    /// Loads the store file of an [encrypted](StoreBuilder::encrypted) store even if it isn't encrypted.
+    ///
+    /// This is meant to migrate a store that was saved before encryption was enabled,
+    /// it is encrypted the next time it is saved. As anyone able to write the store file can then
+    /// replace the store with unencrypted data, only enable it for as long as the migration takes.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::StoreBuilder;
+    ///
+    /// let builder = StoreBuilder::new("secrets.bin".parse()?)
+    ///   .encrypted(|| Ok([0u8; 32]))
+    ///   .allow_unencrypted(true);
+    ///
+    /// # Ok(())
+    /// # }
+    #[cfg(feature = "encryption")]
+    pub fn allow_unencrypted(mut self, allow_unencrypted: bool) -> Self {
+        self.allow_unencrypted = allow_unencrypted;
+        self
+    }
+
+    /// Watches the store file and reloads the store when it is modified outside of the app.
+    ///
+    /// A `store://change` event is emitted for every key whose value changed.
+    /// Only stores registered with the plugin are reloaded.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::StoreBuilder;
+    ///
+    /// let builder = StoreBuilder::new("store.json".parse()?)
+    ///   .watch_file(true);
+    ///
+    /// # Ok(())
+    /// # }
+    #[cfg(feature = "watch")]
+    pub fn watch_file(mut self, watch_file: bool) -> Self {
+        self.watch_file = watch_file;
+        self
+    }
+
+    /// Defines the directory the store is persisted in, the app data directory by default.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::{BaseDirectory, StoreBuilder};
+    ///
+    /// let builder = StoreBuilder::new("settings.json".parse()?)
+    ///   .base_dir(BaseDirectory::AppConfig);
+    ///
+    /// # Ok(())
+    /// # }
+    pub fn base_dir(mut self, base_dir: BaseDirectory) -> Self {
+        self.base_dir = base_dir;
+        self
+    }
+
+    /// Limits the serialized size of the store, in bytes.
+    ///
+    /// Writes that would grow the store past `max_size` fail with [`Error::SizeLimitExceeded`]
+    /// and leave it unchanged. The size is measured with the store format, before encryption.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::StoreBuilder;
+    ///
+    /// let builder = StoreBuilder::new("store.json".parse()?)
+    ///   .max_size(64 * 1024);
+    ///
+    /// # Ok(())
+    /// # }
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Limits the number of keys in the store.
+    ///
+    /// Writes that would add keys past `max_keys` fail with [`Error::KeyLimitExceeded`]
+    /// and leave the store unchanged.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::StoreBuilder;
+    ///
+    /// let builder = StoreBuilder::new("store.json".parse()?)
+    ///   .max_keys(100);
+    ///
+    /// # Ok(())
+    /// # }
+    pub fn max_keys(mut self, max_keys: usize) -> Self {
+        self.max_keys = Some(max_keys);
+        self
+    }
+
+    /// Registers a validator for the values written to `key`.
+    ///
+    /// Writes of values the validator rejects fail with [`Error::Rejected`] and leave the store unchanged.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::StoreBuilder;
+    ///
+    /// let builder = StoreBuilder::new("store.json".parse()?)
+    ///   .validator("volume", |value| match value.as_u64() {
+    ///     Some(volume) if volume <= 100 => Ok(()),
+    ///     _ => Err("volume must be an integer between 0 and 100".into()),
+    ///   });
+    ///
+    /// # Ok(())
+    /// # }
+    pub fn validator<F>(mut self, key: impl Into<String>, validator: F) -> Self
+    where
+        F: Fn(&JsonValue) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.validators.insert(key.into(), Arc::new(validator));
+        self
+    }
+
+    /// Records up to `limit` changes that can be reverted with [`Store::undo`] and reapplied with [`Store::redo`].
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::StoreBuilder;
+    ///
+    /// let builder = StoreBuilder::new("store.json".parse()?)
+    ///   .history(50);
+    ///
+    /// # Ok(())
+    /// # }
+    pub fn history(mut self, limit: usize) -> Self {
+        self.history = Some(limit);
         self
     }
 
@@ -152,6 +756,32 @@ impl<R: Runtime> StoreBuilder<R> {
     /// # Ok(())
     /// # }
     pub fn build(self) -> Store<R> {
+        let auto_save = self
+            .auto_save
+            .map(|debounce| spawn_auto_save(self.app.clone(), self.path.clone(), debounce));
+
+        #[cfg(feature = "watch")]
+        let watcher = if self.watch_file {
+            let watcher =
+                resolve_path(&self.app, &self.base_dir, &self.path).and_then(|store_path| {
+                    store_path
+                        .map(|store_path| {
+                            crate::watch::watch(self.app.clone(), self.path.clone(), store_path)
+                                .map_err(Error::Watch)
+                        })
+                        .transpose()
+                });
+            match watcher {
+                Ok(watcher) => watcher.map(Arc::new),
+                Err(err) => {
+                    warn!("Failed to watch store {:?}: {}", self.path, err);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
         Store {
             app: self.app,
             path: self.path,
@@ -159,6 +789,27 @@ impl<R: Runtime> StoreBuilder<R> {
             cache: self.cache,
             serialize: self.serialize,
             deserialize: self.deserialize,
+            backup: self.backup,
+            auto_save,
+            validate: self.validate,
+            migrations: Arc::new(self.migrations),
+            version: 0,
+            #[cfg(feature = "encryption")]
+            cipher: self.cipher,
+            #[cfg(feature = "encryption")]
+            allow_unencrypted: self.allow_unencrypted,
+            #[cfg(feature = "watch")]
+            _watcher: watcher,
+            listeners: Vec::new(),
+            next_listener_id: 0,
+            expirations: HashMap::new(),
+            base_dir: self.base_dir,
+            revision: 0,
+            max_size: self.max_size,
+            max_keys: self.max_keys,
+            validators: self.validators,
+            history: self.history.map(History::new),
+            saved: Default::default(),
         }
     }
 }
@@ -171,140 +822,1043 @@ pub struct Store<R: Runtime> {
     cache: HashMap<String, JsonValue>,
     serialize: SerializeFn,
     deserialize: DeserializeFn,
+    backup: bool,
+    auto_save: Option<Sender<()>>,
+    validate: Option<ValidateFn>,
+    migrations: Arc<Vec<MigrationFn>>,
+    /// The version of the on-disk state when it was last loaded.
+    version: usize,
+    #[cfg(feature = "encryption")]
+    cipher: Option<Cipher>,
+    #[cfg(feature = "encryption")]
+    allow_unencrypted: bool,
+    #[cfg(feature = "watch")]
+    _watcher: Option<Arc<Debouncer<RecommendedWatcher>>>,
+    listeners: Vec<(u32, String, ChangeListener)>,
+    next_listener_id: u32,
+    /// Expiration timestamps, in milliseconds since the unix epoch, of the keys inserted with a TTL.
+    expirations: HashMap<String, u64>,
+    base_dir: BaseDirectory,
+    /// Incremented on every change, so writers can detect that the store changed since they last read it.
+    pub(crate) revision: u64,
+    max_size: Option<usize>,
+    max_keys: Option<usize>,
+    validators: HashMap<String, KeyValidatorFn>,
+    history: Option<History>,
+    /// The hash of the on-disk state as last loaded or saved, to tell the store's own saves apart from other changes.
+    ///
+    /// Held while saving, so concurrent saves of the store don't write the same temporary file.
+    saved: Arc<Mutex<Option<u64>>>,
 }
 
 impl<R: Runtime> Store<R> {
+    /// Decrypts, if enabled, and deserializes the on-disk state.
+    fn decode(&self, bytes: &[u8]) -> Result<HashMap<String, JsonValue>, Error> {
+        #[cfg(feature = "encryption")]
+        let decrypted;
+        #[cfg(feature = "encryption")]
+        let bytes = match &self.cipher {
+            // stores saved before encryption was enabled are read as is and encrypted on the next save
+            Some(_) if self.allow_unencrypted && !crate::encryption::is_encrypted(bytes) => bytes,
+            Some(cipher) => {
+                decrypted = cipher.decrypt(bytes)?;
+                &decrypted
+            }
+            None => bytes,
+        };
+
+        (self.deserialize)(bytes).map_err(Error::Deserialize)
+    }
+
     /// Update the store from the on-disk state
+    ///
+    /// In-memory stores have no on-disk state, loading them does nothing.
     pub fn load(&mut self) -> Result<(), Error> {
-        let app_dir = self
-            .app
-            .path_resolver()
-            .app_data_dir()
-            .expect("failed to resolve app dir");
-        let store_path = app_dir.join(&self.path);
+        let store_path = match resolve_path(&self.app, &self.base_dir, &self.path)? {
+            Some(store_path) => store_path,
+            None => return Ok(()),
+        };
 
-        let bytes = read(store_path)?;
+        let bytes = read(&store_path)?;
+        self.load_bytes(&store_path, &bytes)
+    }
 
-        self.cache
-            .extend((self.deserialize)(&bytes).map_err(Error::Deserialize)?);
+    /// Updates the store from `bytes`, the contents of the store file at `store_path`.
+    fn load_bytes(&mut self, store_path: &Path, bytes: &[u8]) -> Result<(), Error> {
+        *self.saved.lock()? = Some(hash_bytes(bytes));
+
+        let (mut cache, recovered) = match self.decode(bytes) {
+            Ok(cache) => (cache, false),
+            Err(err) if self.backup => {
+                let backup_path = with_suffix(store_path, ".bak");
+                let cache = read(&backup_path)
+                    .ok()
+                    .and_then(|bytes| self.decode(&bytes).ok())
+                    .ok_or(err)?;
+
+                warn!(
+                    "Store {:?} is corrupted, recovered it from {:?}",
+                    store_path, backup_path
+                );
+                (cache, true)
+            }
+            Err(err) => return Err(err),
+        };
+
+        let version = cache
+            .remove(VERSION_KEY)
+            .and_then(|version| version.as_u64())
+            .unwrap_or_default() as usize;
+        if version > self.migrations.len() {
+            warn!(
+                "Store {:?} has version {} but only {} migrations are known, keeping its version",
+                store_path,
+                version,
+                self.migrations.len()
+            );
+        }
+        let mut expirations: HashMap<String, u64> = cache
+            .remove(EXPIRATIONS_KEY)
+            .and_then(|expirations| serde_json::from_value(expirations).ok())
+            .unwrap_or_default();
+        let now = now_millis();
+        expirations.retain(|key, expires_at| {
+            let expired = *expires_at <= now;
+            if expired {
+                cache.remove(key);
+            }
+            !expired
+        });
+
+        let migrated = version < self.migrations.len();
+        for (version, migration) in self.migrations.iter().enumerate().skip(version) {
+            migration(&mut cache).map_err(|source| Error::Migration {
+                version: version + 1,
+                source,
+            })?;
+        }
+
+        let mut merged_expirations = self.expirations.clone();
+        for key in cache.keys() {
+            match expirations.remove(key) {
+                Some(expires_at) => merged_expirations.insert(key.clone(), expires_at),
+                None => merged_expirations.remove(key),
+            };
+        }
+
+        let mut merged = self.cache.clone();
+        merged.extend(cache);
+        if let Some(validate) = self.validate {
+            validate(&merged)?;
+        }
+        self.cache = merged;
+        self.expirations = merged_expirations;
+        self.version = version;
+        self.revision += 1;
+
+        if recovered {
+            // the store file is still corrupted, backing it up would overwrite the intact backup
+            self.write(false)?;
+        } else if migrated {
+            self.save()?;
+        }
+
+        if recovered {
+            self.app
+                .emit_all("store://recovered", RecoveredPayload { path: &self.path })?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the store with the on-disk state, emitting change events for every key that changed.
+    ///
+    /// Unlike [`Store::load`], keys that are missing on disk are reset to their default value.
+    /// Nothing happens if the file still holds the state the store last loaded or saved,
+    /// and the revision only changes if the on-disk state differs from the store.
+    pub fn reload(&mut self) -> Result<(), Error> {
+        let store_path = match resolve_path(&self.app, &self.base_dir, &self.path)? {
+            Some(store_path) => store_path,
+            None => return Ok(()),
+        };
+
+        let bytes = read(&store_path)?;
+        if *self.saved.lock()? == Some(hash_bytes(&bytes)) {
+            return Ok(());
+        }
+
+        let revision = self.revision;
+        let previous =
+            std::mem::replace(&mut self.cache, self.defaults.clone().unwrap_or_default());
+        let previous_expirations = std::mem::take(&mut self.expirations);
+        if let Err(err) = self.load_bytes(&store_path, &bytes) {
+            self.cache = previous;
+            self.expirations = previous_expirations;
+            return Err(err);
+        }
+        if self.cache == previous && self.expirations == previous_expirations {
+            self.revision = revision;
+            return Ok(());
+        }
+
+        for (key, value) in &previous {
+            if self.cache.get(key) != Some(value) {
+                self.emit_change(key, self.cache.get(key).unwrap_or(&JsonValue::Null))?;
+            }
+        }
+        for (key, value) in &self.cache {
+            if !previous.contains_key(key) {
+                self.emit_change(key, value)?;
+            }
+        }
 
         Ok(())
     }
 
     /// Saves the store to disk
+    ///
+    /// The data is written to a temporary file that is synced and then renamed over the store file,
+    /// so a crash in the middle of a save never leaves a partially written store behind.
+    ///
+    /// In-memory stores are never saved.
     pub fn save(&self) -> Result<(), Error> {
-        let app_dir = self
-            .app
-            .path_resolver()
-            .app_data_dir()
-            .expect("failed to resolve app dir");
-        let store_path = app_dir.join(&self.path);
+        self.write(self.backup)
+    }
+
+    /// Writes the store to disk, copying the previous store file to the backup first if `backup` is set.
+    fn write(&self, backup: bool) -> Result<(), Error> {
+        let store_path = match resolve_path(&self.app, &self.base_dir, &self.path)? {
+            Some(store_path) => store_path,
+            None => return Ok(()),
+        };
+        let mut saved = self.saved.lock()?;
+        let store_dir = store_path
+            .parent()
+            .ok_or_else(|| Error::BaseDirectory(self.path.clone()))?;
 
-        create_dir_all(store_path.parent().expect("invalid store path"))?;
+        create_dir_all(store_dir)?;
 
-        let bytes = (self.serialize)(&self.cache).map_err(Error::Serialize)?;
-        let mut f = File::create(&store_path)?;
+        // never downgrade a store written by a newer version of the app
+        let version = self.version.max(self.migrations.len());
+        let bytes = if version == 0 && self.expirations.is_empty() {
+            (self.serialize)(&self.cache)
+        } else {
+            let now = now_millis();
+            let mut cache: HashMap<String, JsonValue> = self
+                .cache
+                .iter()
+                .filter(|(key, _)| !self.is_expired_at(key, now))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            if version > 0 {
+                cache.insert(VERSION_KEY.to_string(), version.into());
+            }
+            let expirations: serde_json::Map<String, JsonValue> = self
+                .expirations
+                .iter()
+                .filter(|(_, expires_at)| **expires_at > now)
+                .map(|(key, expires_at)| (key.clone(), (*expires_at).into()))
+                .collect();
+            if !expirations.is_empty() {
+                cache.insert(EXPIRATIONS_KEY.to_string(), expirations.into());
+            }
+            (self.serialize)(&cache)
+        }
+        .map_err(Error::Serialize)?;
+        #[cfg(feature = "encryption")]
+        let bytes = match &self.cipher {
+            Some(cipher) => cipher.encrypt(&bytes)?,
+            None => bytes,
+        };
+
+        if backup && store_path.exists() {
+            copy(&store_path, with_suffix(&store_path, ".bak"))?;
+        }
+
+        let tmp_path = with_suffix(&store_path, ".tmp");
+        let mut f = File::create(&tmp_path)?;
         f.write_all(&bytes)?;
+        f.sync_all()?;
+        drop(f);
+
+        rename(&tmp_path, &store_path)?;
+        *saved = Some(hash_bytes(&bytes));
+
+        // persist the rename itself, directories can't be opened as files on windows
+        #[cfg(unix)]
+        File::open(store_dir)?.sync_all()?;
 
         Ok(())
     }
 
-    pub fn insert(&mut self, key: String, value: JsonValue) -> Result<(), Error> {
-        self.cache.insert(key.clone(), value.clone());
-        self.app.emit_all(
-            "store://change",
-            ChangePayload {
-                path: &self.path,
-                key: &key,
-                value: &value,
+    /// Checks the prospective contents of the store against its limits and the validators of the `changed` keys.
+    fn check_limits<'k>(
+        &self,
+        cache: &HashMap<String, JsonValue>,
+        changed: impl IntoIterator<Item = &'k str>,
+    ) -> Result<(), Error> {
+        for key in changed {
+            if let Some(value) = cache.get(key) {
+                self.validate(key, value)?;
+            }
+        }
+        self.check_key_count(cache.len())?;
+        self.check_size(cache)
+    }
+
+    /// Like [`Self::check_limits`] for inserting `value` at `key`, without building the prospective
+    /// contents of the store unless its size is limited.
+    fn check_insert(&self, key: &str, value: &JsonValue) -> Result<(), Error> {
+        self.validate(key, value)?;
+        self.check_key_count(self.cache.len() + usize::from(!self.cache.contains_key(key)))?;
+        if self.max_size.is_some() {
+            let mut cache = self.cache.clone();
+            cache.insert(key.to_string(), value.clone());
+            self.check_size(&cache)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the validator of `key`, if any, on `value`.
+    fn validate(&self, key: &str, value: &JsonValue) -> Result<(), Error> {
+        match self.validators.get(key) {
+            Some(validator) => validator(value).map_err(|source| Error::Rejected {
+                key: key.to_string(),
+                source,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn check_key_count(&self, count: usize) -> Result<(), Error> {
+        match self.max_keys {
+            Some(limit) if count > limit => Err(Error::KeyLimitExceeded { count, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_size(&self, cache: &HashMap<String, JsonValue>) -> Result<(), Error> {
+        if let Some(limit) = self.max_size {
+            let size = (self.serialize)(cache).map_err(Error::Serialize)?.len();
+            if size > limit {
+                return Err(Error::SizeLimitExceeded { size, limit });
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the previous values of the keys touched by a change, if the history is enabled.
+    fn record_history(&mut self, entry: HistoryEntry) {
+        if let Some(history) = &mut self.history {
+            history.record(entry);
+        }
+    }
+
+    /// Restores the values of a history entry, returning the entry reverting it.
+    fn apply_history(&mut self, entry: HistoryEntry) -> HistoryEntry {
+        let inverse = entry
+            .into_iter()
+            .map(|(key, value, expires_at)| {
+                let current_expiration = match expires_at {
+                    Some(expires_at) => self.expirations.insert(key.clone(), expires_at),
+                    None => self.expirations.remove(&key),
+                };
+                let current = match value {
+                    Some(value) => self.cache.insert(key.clone(), value),
+                    None => self.cache.remove(&key),
+                };
+                (key, current, current_expiration)
+            })
+            .collect();
+        self.revision += 1;
+        self.schedule_save();
+        inverse
+    }
+
+    fn emit_history(&self, entry: &HistoryEntry) -> Result<(), Error> {
+        for (key, _, _) in entry {
+            self.emit_change(key, self.cache.get(key).unwrap_or(&JsonValue::Null))?;
+        }
+        Ok(())
+    }
+
+    /// Reverts the last recorded change, emitting a change event for every key it touched.
+    ///
+    /// Returns `false` if there is nothing to undo or the history isn't enabled with [`StoreBuilder::history`].
+    pub fn undo(&mut self) -> Result<bool, Error> {
+        let entry = match self.history.as_mut().and_then(History::pop_undo) {
+            Some(entry) => entry,
+            None => return Ok(false),
+        };
+        let inverse = self.apply_history(entry);
+        let result = self.emit_history(&inverse);
+        if let Some(history) = &mut self.history {
+            history.push_redo(inverse);
+        }
+        result.map(|()| true)
+    }
+
+    /// Reapplies the last change reverted with [`Store::undo`], emitting a change event for every key it touched.
+    ///
+    /// Returns `false` if there is nothing to redo. Any new change discards the changes that can be redone.
+    pub fn redo(&mut self) -> Result<bool, Error> {
+        let entry = match self.history.as_mut().and_then(History::pop_redo) {
+            Some(entry) => entry,
+            None => return Ok(false),
+        };
+        let inverse = self.apply_history(entry);
+        let result = self.emit_history(&inverse);
+        if let Some(history) = &mut self.history {
+            history.push_undo(inverse);
+        }
+        result.map(|()| true)
+    }
+
+    /// Returns `true` if there is a change to revert with [`Store::undo`].
+    pub fn can_undo(&self) -> bool {
+        matches!(&self.history, Some(history) if history.can_undo())
+    }
+
+    /// Returns `true` if there is a change to reapply with [`Store::redo`].
+    pub fn can_redo(&self) -> bool {
+        matches!(&self.history, Some(history) if history.can_redo())
+    }
+
+    /// Notifies the auto save thread, if any, that the store changed.
+    fn schedule_save(&self) {
+        if let Some(auto_save) = &self.auto_save {
+            let _ = auto_save.send(());
+        }
+    }
+
+    /// Notifies the Rust listeners and the subscribed windows that `key` changed.
+    fn emit_change(&self, key: &str, value: &JsonValue) -> Result<(), Error> {
+        self.notify_listeners(key, value);
+        subscription::emit_change(
+            &self.app,
+            &self.path,
+            self.revision,
+            Change {
+                key,
+                pointer: None,
+                value,
             },
-        )?;
+        )
+    }
+
+    /// Notifies the Rust listeners of every change and the subscribed windows with a single `store://changes` event.
+    fn emit_changes(&self, changes: Vec<Change<'_>>) -> Result<(), Error> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        for change in &changes {
+            self.notify_listeners(change.key, change.value);
+        }
+        subscription::emit_changes(&self.app, &self.path, self.revision, changes)
+    }
+
+    fn notify_listeners(&self, key: &str, value: &JsonValue) {
+        for (_, prefix, listener) in &self.listeners {
+            if key.starts_with(prefix.as_str()) {
+                listener(key, value);
+            }
+        }
+    }
+
+    /// Returns `true` if listeners were registered with [`Store::on_change`].
+    pub(crate) fn has_listeners(&self) -> bool {
+        !self.listeners.is_empty()
+    }
+
+    /// Registers a Rust listener called whenever a key starting with `prefix` changes.
+    ///
+    /// Pass the full key to listen to a single key, or an empty prefix to listen to every key.
+    /// Deleted keys are reported with a `null` value. Returns an id to remove the listener with [`Store::unlisten`].
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::StoreBuilder;
+    ///
+    /// let mut store = StoreBuilder::new("store.json".parse()?).build();
+    ///
+    /// store.on_change("settings.", |key, value| {
+    ///   println!("{key} changed to {value}");
+    /// });
+    ///
+    /// # Ok(())
+    /// # }
+    pub fn on_change<F>(&mut self, prefix: impl Into<String>, listener: F) -> u32
+    where
+        F: Fn(&str, &JsonValue) + Send + Sync + 'static,
+    {
+        let id = self.next_listener_id;
+        self.next_listener_id += 1;
+        self.listeners.push((id, prefix.into(), Arc::new(listener)));
+        id
+    }
+
+    /// Removes a listener registered with [`Store::on_change`].
+    pub fn unlisten(&mut self, id: u32) -> bool {
+        let len = self.listeners.len();
+        self.listeners
+            .retain(|(listener_id, _, _)| *listener_id != id);
+        self.listeners.len() != len
+    }
+
+    fn is_expired_at(&self, key: &str, now: u64) -> bool {
+        matches!(self.expirations.get(key), Some(expires_at) if *expires_at <= now)
+    }
+
+    /// Returns `true` if any key has a TTL, expired or not.
+    pub(crate) fn has_ttl(&self) -> bool {
+        !self.expirations.is_empty()
+    }
+
+    /// Returns `true` if a key's TTL elapsed but it wasn't purged yet.
+    pub(crate) fn has_expired(&self) -> bool {
+        let now = now_millis();
+        self.expirations
+            .values()
+            .any(|expires_at| *expires_at <= now)
+    }
+
+    /// Removes the keys whose TTL elapsed, emitting a change event with a `null` value for each of them.
+    ///
+    /// Expired keys are already hidden from all accessors, this only frees them and notifies listeners.
+    /// Once a store has keys with a TTL, the plugin purges the stores it loaded every second.
+    pub fn purge_expired(&mut self) -> Result<(), Error> {
+        let now = now_millis();
+        let expired: Vec<String> = self
+            .expirations
+            .iter()
+            .filter(|(_, expires_at)| **expires_at <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in expired {
+            self.expirations.remove(&key);
+            if self.cache.remove(&key).is_some() {
+                self.revision += 1;
+                self.schedule_save();
+                self.emit_change(&key, &JsonValue::Null)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Inserts a key-value pair into the store.
+    ///
+    /// Fails without changing the store if the value is rejected by the validator of `key`
+    /// or the store would exceed its size or key limits.
+    pub fn insert(&mut self, key: String, value: JsonValue) -> Result<(), Error> {
+        self.check_insert(&key, &value)?;
+        let previous = self.cache.insert(key.clone(), value.clone());
+        let previous_expiration = self.expirations.remove(&key);
+        self.record_history(vec![(key.clone(), previous, previous_expiration)]);
+        self.revision += 1;
+        self.schedule_save();
+        self.emit_change(&key, &value)?;
+
+        Ok(())
+    }
+
+    /// Inserts a key-value pair that expires after `ttl`.
+    ///
+    /// Once expired, the key is hidden from all accessors and removed on the next [`Store::purge_expired`],
+    /// which the plugin runs every second for the stores it loaded once the first TTL is set.
+    /// Writing the key again without a TTL, with [`Store::insert`] or [`Store::patch`], clears its TTL.
+    pub fn insert_with_ttl(
+        &mut self,
+        key: String,
+        value: JsonValue,
+        ttl: Duration,
+    ) -> Result<(), Error> {
+        self.insert(key.clone(), value)?;
+        self.expirations
+            .insert(key, now_millis().saturating_add(ttl.as_millis() as u64));
 
         Ok(())
     }
 
+    /// Inserts a key-value pair only if the store is still at `expected_revision`.
+    ///
+    /// Returns the new revision, or [`Error::RevisionMismatch`] without changing the store if another
+    /// write happened since `expected_revision` was read.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::StoreBuilder;
+    ///
+    /// let mut store = StoreBuilder::new("store.json".parse()?).build();
+    ///
+    /// let revision = store.revision();
+    /// store.compare_and_set(revision, "theme".to_string(), "dark".into())?;
+    ///
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compare_and_set(
+        &mut self,
+        expected_revision: u64,
+        key: String,
+        value: JsonValue,
+    ) -> Result<u64, Error> {
+        if self.revision != expected_revision {
+            return Err(Error::RevisionMismatch {
+                expected: expected_revision,
+                actual: self.revision,
+            });
+        }
+        self.insert(key, value)?;
+        Ok(self.revision)
+    }
+
+    /// Returns the current revision of the store, incremented on every change.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Returns the value for the given `key`.
     pub fn get(&self, key: impl AsRef<str>) -> Option<&JsonValue> {
-        self.cache.get(key.as_ref())
+        let key = key.as_ref();
+        self.cache
+            .get(key)
+            .filter(|_| !self.is_expired_at(key, now_millis()))
+    }
+
+    /// Returns the value at a JSON pointer into the store, whose first segment is the key.
+    ///
+    /// E.g. `/settings/theme` returns the `theme` field of the value stored under the `settings` key.
+    pub fn get_pointer(&self, pointer: impl AsRef<str>) -> Option<&JsonValue> {
+        let (key, pointer) = split_pointer(pointer.as_ref())?;
+        self.get(key)?.pointer(pointer)
+    }
+
+    /// Applies RFC 6902 JSON Patch operations to the store, treating it as a single JSON object.
+    ///
+    /// If any operation fails, none are applied. Otherwise a single `store://changes` event is emitted,
+    /// listing the JSON pointer each operation changed.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::{JsonPatch, StoreBuilder};
+    /// use serde_json::json;
+    ///
+    /// let mut store = StoreBuilder::new("store.json".parse()?).build();
+    ///
+    /// let patch: JsonPatch = serde_json::from_value(json!([
+    ///   { "op": "replace", "path": "/settings/theme", "value": "dark" },
+    /// ]))?;
+    /// store.patch(&patch)?;
+    ///
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn patch(&mut self, patch: &Patch) -> Result<(), Error> {
+        self.purge_expired()?;
+
+        let mut document = JsonValue::Object(
+            self.cache
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        );
+        json_patch::patch(&mut document, &patch.0).map_err(|err| Error::Patch(err.into()))?;
+        let cache: HashMap<String, JsonValue> = match document {
+            JsonValue::Object(map) => map.into_iter().collect(),
+            _ => return Err(Error::Patch("the store must remain a JSON object".into())),
+        };
+
+        // the keys and pointers that changed, `None` if the whole value of the key may have changed
+        let mut changed: Vec<(String, Option<&str>)> = Vec::new();
+        for operation in &patch.0 {
+            let paths = match operation {
+                PatchOperation::Add(op) => vec![op.path.as_str()],
+                PatchOperation::Remove(op) => vec![op.path.as_str()],
+                PatchOperation::Replace(op) => vec![op.path.as_str()],
+                PatchOperation::Move(op) => vec![op.from.as_str(), op.path.as_str()],
+                PatchOperation::Copy(op) => vec![op.path.as_str()],
+                PatchOperation::Test(_) => vec![],
+            };
+            for path in paths {
+                match split_pointer(path) {
+                    Some((key, _)) => changed.push((key, Some(path))),
+                    // the whole store was replaced
+                    None => changed.extend(
+                        self.cache
+                            .keys()
+                            .chain(cache.keys())
+                            .filter(|key| self.cache.get(*key) != cache.get(*key))
+                            .map(|key| (key.clone(), None)),
+                    ),
+                }
+            }
+        }
+        let mut seen = Vec::new();
+        changed.retain(|change| {
+            let first = !seen.contains(change);
+            seen.push(change.clone());
+            first
+        });
+
+        self.check_limits(&cache, changed.iter().map(|(key, _)| key.as_str()))?;
+
+        let mut entry: HistoryEntry = Vec::new();
+        for (key, _) in &changed {
+            // keys written by the patch lose their TTL, like with `insert`, so undo has to restore it
+            let written =
+                self.cache.get(key) != cache.get(key) || self.expirations.contains_key(key);
+            if written && !entry.iter().any(|(k, _, _)| k == key) {
+                entry.push((
+                    key.clone(),
+                    self.cache.get(key).cloned(),
+                    self.expirations.get(key).copied(),
+                ));
+            }
+        }
+        self.record_history(entry);
+
+        self.cache = cache;
+        for (key, _) in &changed {
+            self.expirations.remove(key);
+        }
+        if changed.is_empty() {
+            return Ok(());
+        }
+        self.revision += 1;
+        self.schedule_save();
+
+        let changes: Vec<Change<'_>> = changed
+            .iter()
+            .map(|(key, pointer)| {
+                let value = self.cache.get(key).and_then(|value| match pointer {
+                    Some(pointer) => {
+                        split_pointer(pointer).and_then(|(_, pointer)| value.pointer(pointer))
+                    }
+                    None => Some(value),
+                });
+                Change {
+                    key,
+                    pointer: *pointer,
+                    value: value.unwrap_or(&JsonValue::Null),
+                }
+            })
+            .collect();
+
+        let mut notified: Vec<&str> = Vec::new();
+        for change in &changes {
+            if !notified.contains(&change.key) {
+                notified.push(change.key);
+                self.notify_listeners(
+                    change.key,
+                    self.cache.get(change.key).unwrap_or(&JsonValue::Null),
+                );
+            }
+        }
+        subscription::emit_changes(&self.app, &self.path, self.revision, changes)
+    }
+
+    /// Applies several changes to the store at once.
+    ///
+    /// If `f` returns an error, or the result breaks the limits or validators of the store,
+    /// every change made through the [`Transaction`] is rolled back. Otherwise a single `store://changes` event listing all changed keys is emitted.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::StoreBuilder;
+    /// use serde_json::json;
+    ///
+    /// let mut store = StoreBuilder::new("store.json".parse()?).build();
+    ///
+    /// store.transaction(|tx| {
+    ///   tx.insert("theme".to_string(), json!("dark"));
+    ///   tx.delete("legacy-theme");
+    ///   Ok(())
+    /// })?;
+    ///
+    /// # Ok(())
+    /// # }
+    pub fn transaction<T, F>(&mut self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Transaction<'_>) -> Result<T, Error>,
+    {
+        self.purge_expired()?;
+
+        let mut tx = Transaction {
+            cache: &mut self.cache,
+            original: HashMap::new(),
+        };
+        let result = f(&mut tx);
+        let original = tx.original;
+        let result = result.and_then(|value| {
+            self.check_limits(&self.cache, original.keys().map(String::as_str))?;
+            Ok(value)
+        });
+
+        let value = match result {
+            Ok(value) => value,
+            Err(err) => {
+                for (key, value) in original {
+                    match value {
+                        Some(value) => self.cache.insert(key, value),
+                        None => self.cache.remove(&key),
+                    };
+                }
+                return Err(err);
+            }
+        };
+
+        let entry: HistoryEntry = original
+            .iter()
+            .filter(|(key, value)| self.cache.get(*key) != value.as_ref())
+            .map(|(key, value)| {
+                (
+                    key.clone(),
+                    value.clone(),
+                    self.expirations.get(key).copied(),
+                )
+            })
+            .collect();
+        self.record_history(entry);
+        for key in original.keys() {
+            self.expirations.remove(key);
+        }
+
+        let changes: Vec<Change<'_>> = original
+            .iter()
+            .filter(|(key, value)| self.cache.get(*key) != value.as_ref())
+            .map(|(key, _)| Change {
+                key,
+                pointer: None,
+                value: self.cache.get(key).unwrap_or(&JsonValue::Null),
+            })
+            .collect();
+        if !changes.is_empty() {
+            self.revision += 1;
+            self.schedule_save();
+            self.emit_changes(changes)?;
+        }
+
+        Ok(value)
+    }
+
+    /// Returns the value for the given `key` deserialized into `T`.
+    pub fn get_as<T: DeserializeOwned>(&self, key: impl AsRef<str>) -> Result<Option<T>, Error> {
+        let key = key.as_ref();
+        self.get(key)
+            .map(|value| {
+                T::deserialize(value).map_err(|source| Error::InvalidValue {
+                    key: key.to_string(),
+                    source,
+                })
+            })
+            .transpose()
+    }
+
+    /// Serializes `value` and inserts it under the given `key`.
+    pub fn insert_as<T: Serialize>(&mut self, key: String, value: T) -> Result<(), Error> {
+        let value = serde_json::to_value(value)?;
+        self.insert(key, value)
     }
 
     pub fn has(&self, key: impl AsRef<str>) -> bool {
-        self.cache.contains_key(key.as_ref())
+        self.get(key).is_some()
     }
 
     pub fn delete(&mut self, key: impl AsRef<str>) -> Result<bool, Error> {
-        let flag = self.cache.remove(key.as_ref()).is_some();
+        let previous_expiration = self.expirations.remove(key.as_ref());
+        let previous = self.cache.remove(key.as_ref());
+        let flag = previous.is_some();
         if flag {
-            self.app.emit_all(
-                "store://change",
-                ChangePayload {
-                    path: &self.path,
-                    key: key.as_ref(),
-                    value: &JsonValue::Null,
-                },
-            )?;
+            self.record_history(vec![(
+                key.as_ref().to_string(),
+                previous,
+                previous_expiration,
+            )]);
+            self.revision += 1;
+            self.schedule_save();
+            self.emit_change(key.as_ref(), &JsonValue::Null)?;
         }
         Ok(flag)
     }
 
+    /// Removes every key, emitting a single `store://changes` event.
     pub fn clear(&mut self) -> Result<(), Error> {
-        let keys: Vec<String> = self.cache.keys().cloned().collect();
-        self.cache.clear();
-        for key in keys {
-            self.app.emit_all(
-                "store://change",
-                ChangePayload {
-                    path: &self.path,
-                    key: &key,
+        let previous = std::mem::take(&mut self.cache);
+        let mut expirations = std::mem::take(&mut self.expirations);
+        let keys: Vec<String> = previous.keys().cloned().collect();
+        self.record_history(
+            previous
+                .into_iter()
+                .map(|(key, value)| {
+                    let expires_at = expirations.remove(&key);
+                    (key, Some(value), expires_at)
+                })
+                .collect(),
+        );
+        self.revision += 1;
+        self.schedule_save();
+        self.emit_changes(
+            keys.iter()
+                .map(|key| Change {
+                    key,
+                    pointer: None,
                     value: &JsonValue::Null,
-                },
-            )?;
-        }
-        Ok(())
+                })
+                .collect(),
+        )
     }
 
+    /// Replaces the store with its defaults, or clears it if it has none, emitting a single `store://changes` event.
     pub fn reset(&mut self) -> Result<(), Error> {
-        let has_defaults = self.defaults.is_some();
-
-        if has_defaults {
-            if let Some(defaults) = &self.defaults {
-                for (key, value) in &self.cache {
-                    if defaults.get(key) != Some(value) {
-                        let _ = self.app.emit_all(
-                            "store://change",
-                            ChangePayload {
-                                path: &self.path,
-                                key,
-                                value: defaults.get(key).unwrap_or(&JsonValue::Null),
-                            },
-                        );
-                    }
-                }
-                self.cache = defaults.clone();
-            }
-            Ok(())
-        } else {
-            self.clear()
-        }
+        let defaults = match &self.defaults {
+            Some(defaults) => defaults.clone(),
+            None => return self.clear(),
+        };
+
+        let entry: HistoryEntry = self
+            .cache
+            .keys()
+            .chain(defaults.keys().filter(|key| !self.cache.contains_key(*key)))
+            .filter(|key| self.cache.get(*key) != defaults.get(*key))
+            .map(|key| {
+                (
+                    key.clone(),
+                    self.cache.get(key).cloned(),
+                    self.expirations.get(key).copied(),
+                )
+            })
+            .collect();
+        let keys: Vec<String> = entry.iter().map(|(key, _, _)| key.clone()).collect();
+        self.record_history(entry);
+
+        self.cache = defaults;
+        self.expirations.clear();
+        self.revision += 1;
+        self.schedule_save();
+        self.emit_changes(
+            keys.iter()
+                .map(|key| Change {
+                    key,
+                    pointer: None,
+                    value: self.cache.get(key).unwrap_or(&JsonValue::Null),
+                })
+                .collect(),
+        )
     }
 
     pub fn keys(&self) -> impl Iterator<Item = &String> {
-        self.cache.keys()
+        self.entries().map(|(key, _)| key)
     }
 
     pub fn values(&self) -> impl Iterator<Item = &JsonValue> {
-        self.cache.values()
+        self.entries().map(|(_, value)| value)
     }
 
     pub fn entries(&self) -> impl Iterator<Item = (&String, &JsonValue)> {
-        self.cache.iter()
+        let now = now_millis();
+        self.cache
+            .iter()
+            .filter(move |(key, _)| !self.is_expired_at(key, now))
+    }
+
+    /// Returns a snapshot of every entry of the store, e.g. to back it up or move it to another store.
+    pub fn export(&self) -> HashMap<String, JsonValue> {
+        self.entries()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
+    /// Imports entries, e.g. from [`Store::export`], combining them with the existing ones according to `strategy`.
+    ///
+    /// The import is applied as a single [`Store::transaction`]: either every entry is imported
+    /// and one `store://changes` event is emitted, or the store is left unchanged.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tauri_plugin_store::{MergeStrategy, StoreBuilder};
+    ///
+    /// let source = StoreBuilder::new("old.json".parse()?).build();
+    /// let mut store = StoreBuilder::new("store.json".parse()?).build();
+    ///
+    /// store.import(source.export(), MergeStrategy::KeepExisting)?;
+    ///
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn import(
+        &mut self,
+        data: HashMap<String, JsonValue>,
+        strategy: MergeStrategy,
+    ) -> Result<(), Error> {
+        self.transaction(|tx| {
+            for (key, value) in data {
+                match strategy {
+                    MergeStrategy::Overwrite => tx.insert(key, value),
+                    MergeStrategy::KeepExisting => {
+                        if !tx.has(&key) {
+                            tx.insert(key, value);
+                        }
+                    }
+                    MergeStrategy::DeepMerge => match tx.get(&key).cloned() {
+                        Some(mut existing) => {
+                            deep_merge(&mut existing, value);
+                            tx.insert(key, existing);
+                        }
+                        None => tx.insert(key, value),
+                    },
+                }
+            }
+            Ok(())
+        })
     }
 
     pub fn len(&self) -> usize {
-        self.cache.len()
+        self.entries().count()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.cache.is_empty()
+        self.len() == 0
+    }
+}
+
+/// Changes applied through [`Store::transaction`].
+pub struct Transaction<'a> {
+    cache: &'a mut HashMap<String, JsonValue>,
+    /// The values of the modified keys before the transaction started.
+    original: HashMap<String, Option<JsonValue>>,
+}
+
+impl Transaction<'_> {
+    fn touch(&mut self, key: &str) {
+        if !self.original.contains_key(key) {
+            self.original
+                .insert(key.to_string(), self.cache.get(key).cloned());
+        }
+    }
+
+    pub fn insert(&mut self, key: String, value: JsonValue) {
+        self.touch(&key);
+        self.cache.insert(key, value);
+    }
+
+    pub fn get(&self, key: impl AsRef<str>) -> Option<&JsonValue> {
+        self.cache.get(key.as_ref())
+    }
+
+    pub fn has(&self, key: impl AsRef<str>) -> bool {
+        self.cache.contains_key(key.as_ref())
+    }
+
+    pub fn delete(&mut self, key: impl AsRef<str>) -> bool {
+        self.touch(key.as_ref());
+        self.cache.remove(key.as_ref()).is_some()
     }
 }
 
@@ -317,3 +1871,117 @@ impl<R: Runtime> std::fmt::Debug for Store<R> {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tauri::test::{mock_app, MockRuntime};
+
+    /// Returns an empty directory for the store files of the test `name`.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "tauri-plugin-store-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn store(app: &AppHandle<MockRuntime>, dir: &Path, path: &str) -> StoreBuilder<MockRuntime> {
+        StoreBuilder::new(app.clone(), PathBuf::from(path))
+            .base_dir(BaseDirectory::Path(dir.to_path_buf()))
+    }
+
+    #[test]
+    fn save_after_recovery_keeps_the_backup() {
+        let app = mock_app();
+        let app = app.handle();
+        let dir = test_dir("recovery");
+
+        let mut original = store(&app, &dir, "store.json").backup(true).build();
+        original.insert("a".to_string(), json!(1)).unwrap();
+        original.save().unwrap();
+        // the second save copies the first one to the backup
+        original.save().unwrap();
+        std::fs::write(dir.join("store.json"), b"{ corrupted").unwrap();
+
+        let mut recovered = store(&app, &dir, "store.json").backup(true).build();
+        recovered.load().unwrap();
+        assert_eq!(recovered.get("a"), Some(&json!(1)));
+        recovered.insert("b".to_string(), json!(2)).unwrap();
+        recovered.save().unwrap();
+
+        for path in ["store.json", "store.json.bak"] {
+            let mut store = store(&app, &dir, path).build();
+            store.load().unwrap();
+            assert_eq!(store.get("a"), Some(&json!(1)), "{} lost its data", path);
+        }
+    }
+
+    /// Returns the events emitted on this thread by `f`, with the number of changes each of them lists.
+    fn emitted(f: impl FnOnce()) -> Vec<(&'static str, usize)> {
+        subscription::EMITTED.with(|emitted| emitted.borrow_mut().clear());
+        f();
+        subscription::EMITTED.with(|emitted| emitted.borrow_mut().drain(..).collect())
+    }
+
+    #[test]
+    fn patch_clears_the_ttl_of_written_keys() {
+        let app = mock_app();
+        let mut store = StoreBuilder::new(app.handle(), PathBuf::from("ttl.json"))
+            .base_dir(BaseDirectory::InMemory)
+            .build();
+        let ttl = Duration::from_millis(10);
+        store
+            .insert_with_ttl("patched".to_string(), json!(1), ttl)
+            .unwrap();
+        store
+            .insert_with_ttl("kept".to_string(), json!(1), ttl)
+            .unwrap();
+
+        let patch: Patch = serde_json::from_value(json!([
+          { "op": "replace", "path": "/patched", "value": 2 },
+        ]))
+        .unwrap();
+        store.patch(&patch).unwrap();
+        std::thread::sleep(ttl * 2);
+
+        assert_eq!(store.get("patched"), Some(&json!(2)));
+        assert_eq!(store.get("kept"), None);
+    }
+
+    #[test]
+    fn clear_emits_a_single_event() {
+        let app = mock_app();
+        let mut store = StoreBuilder::new(app.handle(), PathBuf::from("clear.json"))
+            .base_dir(BaseDirectory::InMemory)
+            .build();
+        for i in 0..10 {
+            store.insert(format!("key-{}", i), json!(i)).unwrap();
+        }
+
+        let events = emitted(|| store.clear().unwrap());
+        assert_eq!(events, vec![("store://changes", 10)]);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn reset_emits_a_single_event() {
+        let app = mock_app();
+        let mut store = StoreBuilder::new(app.handle(), PathBuf::from("reset.json"))
+            .base_dir(BaseDirectory::InMemory)
+            .default("theme".to_string(), json!("light"))
+            .build();
+        store.insert("theme".to_string(), json!("dark")).unwrap();
+        for i in 0..10 {
+            store.insert(format!("key-{}", i), json!(i)).unwrap();
+        }
+
+        let events = emitted(|| store.reset().unwrap());
+        assert_eq!(events, vec![("store://changes", 11)]);
+        assert_eq!(store.get("theme"), Some(&json!("light")));
+    }
+}
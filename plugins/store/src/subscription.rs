@@ -0,0 +1,171 @@
+// Copyright 2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use crate::{Change, ChangePayload, ChangesPayload, Error};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    },
+};
+use tauri::{AppHandle, Manager, Runtime};
+
+pub(crate) type SubscriptionId = u32;
+
+#[cfg(test)]
+thread_local! {
+    /// The events emitted on this thread, with the number of changes each of them lists.
+    pub(crate) static EMITTED: std::cell::RefCell<Vec<(&'static str, usize)>> = Default::default();
+}
+
+/// A window listening to changes of a store.
+struct Subscription {
+    window: String,
+    path: PathBuf,
+    /// The keys the window is interested in, `None` subscribes to every key.
+    keys: Option<Vec<String>>,
+}
+
+impl Subscription {
+    fn matches(&self, key: &str) -> bool {
+        match &self.keys {
+            Some(keys) => keys.iter().any(|k| k == key),
+            None => true,
+        }
+    }
+}
+
+/// The change subscriptions of all windows.
+#[derive(Default)]
+pub(crate) struct Subscriptions {
+    next_id: AtomicU32,
+    subscriptions: Mutex<HashMap<SubscriptionId, Subscription>>,
+}
+
+impl Subscriptions {
+    pub(crate) fn subscribe(
+        &self,
+        window: String,
+        path: PathBuf,
+        keys: Option<Vec<String>>,
+    ) -> Result<SubscriptionId, Error> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.subscriptions
+            .lock()?
+            .insert(id, Subscription { window, path, keys });
+        Ok(id)
+    }
+
+    pub(crate) fn unsubscribe(&self, id: SubscriptionId) -> Result<bool, Error> {
+        Ok(self.subscriptions.lock()?.remove(&id).is_some())
+    }
+
+    /// Removes all subscriptions of a window, e.g. once it's destroyed.
+    pub(crate) fn unsubscribe_window(&self, window: &str) -> Result<(), Error> {
+        self.subscriptions
+            .lock()?
+            .retain(|_, subscription| subscription.window != window);
+        Ok(())
+    }
+
+    /// Returns the windows subscribed to the store at `path`, with the changes each of them subscribed to.
+    fn windows_for<'a>(
+        &self,
+        path: &Path,
+        changes: &[Change<'a>],
+    ) -> Result<HashMap<String, Vec<Change<'a>>>, Error> {
+        let subscriptions = self.subscriptions.lock()?;
+
+        let mut windows: HashMap<String, Vec<Change<'a>>> = HashMap::new();
+        for subscription in subscriptions.values().filter(|s| s.path == path) {
+            let window_changes = windows.entry(subscription.window.clone()).or_default();
+            for change in changes {
+                if subscription.matches(change.key)
+                    && !window_changes
+                        .iter()
+                        .any(|c| c.key == change.key && c.pointer == change.pointer)
+                {
+                    window_changes.push(*change);
+                }
+            }
+        }
+        windows.retain(|_, changes| !changes.is_empty());
+        Ok(windows)
+    }
+}
+
+/// Emits `store://change` to the windows subscribed to `key` of the store at `path`.
+pub(crate) fn emit_change<R: Runtime>(
+    app: &AppHandle<R>,
+    path: &Path,
+    revision: u64,
+    change: Change<'_>,
+) -> Result<(), Error> {
+    #[cfg(test)]
+    EMITTED.with(|emitted| emitted.borrow_mut().push(("store://change", 1)));
+
+    let payload = ChangePayload {
+        path,
+        revision,
+        key: change.key,
+        pointer: change.pointer,
+        value: change.value,
+    };
+
+    match app.try_state::<Subscriptions>() {
+        Some(subscriptions) => {
+            for window in subscriptions.windows_for(path, &[change])?.keys() {
+                app.emit_to(window, "store://change", payload.clone())?;
+            }
+        }
+        // the plugin isn't initialized, so nobody could have subscribed
+        None => app.emit_all("store://change", payload)?,
+    }
+
+    Ok(())
+}
+
+/// Emits a single `store://changes` event to every window subscribed to any of the changed keys,
+/// listing only the changes the window subscribed to.
+pub(crate) fn emit_changes<R: Runtime>(
+    app: &AppHandle<R>,
+    path: &Path,
+    revision: u64,
+    changes: Vec<Change<'_>>,
+) -> Result<(), Error> {
+    #[cfg(test)]
+    EMITTED.with(|emitted| {
+        emitted
+            .borrow_mut()
+            .push(("store://changes", changes.len()))
+    });
+
+    match app.try_state::<Subscriptions>() {
+        Some(subscriptions) => {
+            for (window, changes) in subscriptions.windows_for(path, &changes)? {
+                app.emit_to(
+                    &window,
+                    "store://changes",
+                    ChangesPayload {
+                        path,
+                        revision,
+                        changes,
+                    },
+                )?;
+            }
+        }
+        None => app.emit_all(
+            "store://changes",
+            ChangesPayload {
+                path,
+                revision,
+                changes,
+            },
+        )?,
+    }
+
+    Ok(())
+}
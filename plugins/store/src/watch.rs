@@ -0,0 +1,70 @@
+// Copyright 2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use crate::{Error, StoreCollection};
+use log::warn;
+use notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Watches the file at `store_path` and reloads the store registered under `path` when it changes.
+///
+/// The parent directory is watched instead of the file itself,
+/// because saving a store replaces the file instead of writing to it.
+pub(crate) fn watch<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    store_path: PathBuf,
+) -> notify::Result<Debouncer<RecommendedWatcher>> {
+    let file_name = store_path.file_name().map(ToOwned::to_owned);
+    let store_dir = store_path
+        .parent()
+        .map(ToOwned::to_owned)
+        .unwrap_or_default();
+
+    let mut debouncer = new_debouncer(
+        Duration::from_millis(100),
+        move |events: DebounceEventResult| {
+            let changed = match events {
+                Ok(events) => events
+                    .iter()
+                    .any(|event| event.path.file_name() == file_name.as_deref()),
+                Err(err) => {
+                    warn!("Failed to watch store {:?}: {}", path, err);
+                    false
+                }
+            };
+            if !changed {
+                return;
+            }
+
+            if let Err(err) = reload(&app, &path) {
+                warn!("Failed to reload store {:?}: {}", path, err);
+            }
+        },
+    )?;
+
+    std::fs::create_dir_all(&store_dir)?;
+    debouncer
+        .watcher()
+        .watch(&store_dir, RecursiveMode::NonRecursive)?;
+
+    Ok(debouncer)
+}
+
+/// Reloads the store at `path` if it is loaded by the plugin.
+fn reload<R: Runtime>(app: &AppHandle<R>, path: &Path) -> Result<(), Error> {
+    if let Some(collection) = app.try_state::<StoreCollection<R>>() {
+        if let Some(lock) = collection.loaded(path)? {
+            if let Some(store) = lock.write()?.as_mut() {
+                store.reload()?;
+            }
+        }
+    }
+    Ok(())
+}